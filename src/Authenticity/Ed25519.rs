@@ -391,6 +391,10 @@ pub fn Ed25519_verify_sign(
     let (r_bytes, s_bytes) = signature.split_at(32);
     let R: Ed25519 = Ed25519::from_byte_array(r_bytes.try_into().expect("64 / 2 = 32")).ok_or(AuthenticationError::BadSignature)?;
     let S: U256 = U256::from_le_bytes(s_bytes.try_into().expect("64 / 2 = 32")); // Will always be [u8; 32]
+    // S has to be reduced, otherwise S + ORDER is another valid signature for the same message
+    if S >= Ed25519::ORDER {
+        return Err(AuthenticationError::BadSignature);
+    }
 
     let A = Ed25519::from_byte_array(public_key).ok_or(AuthenticationError::BadPublicKey)?;
 
@@ -559,4 +563,23 @@ mod tests {
             Ok(())
         );
     }
+
+    #[test]
+    fn Ed25519_non_canonical_s_test() {
+        // RFC 8032 test 1 with S + ORDER instead of S, the same point but a second encoding
+        let public_key: [u8; 32] = const_decode_to_array(
+            b"d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        )
+        .unwrap();
+        let signature: [u8; 64] = const_decode_to_array(b"e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+        let S = U256::from_le_bytes(signature[32..].try_into().unwrap());
+        let mut malleated = signature;
+        malleated[32..].copy_from_slice(&S.wrapping_add(&Ed25519::ORDER).to_le_bytes());
+
+        assert_eq!(Ed25519_verify_sign(b"", signature, public_key), Ok(()));
+        assert_eq!(
+            Ed25519_verify_sign(b"", malleated, public_key),
+            Err(AuthenticationError::BadSignature)
+        );
+    }
 }
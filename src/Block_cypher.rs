@@ -2,6 +2,7 @@ use std::ops::BitXor;
 
 use crate::Integrity::Sponge::zip_with;
 use std::iter::successors;
+use thiserror::Error;
 
 pub trait BlockCypher<const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize> {
     fn encrypt_block(
@@ -14,11 +15,23 @@ pub trait BlockCypher<const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize
     ) -> [u8; BLOCK_SIZE_BYTES];
 }
 
+#[derive(Debug, Error, PartialEq, Eq, Clone, Copy)]
+pub enum PaddingError {
+    #[error("Decrypted text did not end in valid padding")]
+    BadPadding,
+}
+
 pub trait Padding<const BLOCK_SIZE_BYTES: usize> {
     fn pad(data: &[u8]) -> impl Iterator<Item = [u8; BLOCK_SIZE_BYTES]>;
     fn unpad(data: &[[u8; BLOCK_SIZE_BYTES]]) -> Vec<u8>;
 }
 
+/// A padding that can tell when data doesn't end in valid padding, needed by the `*_try_decrypt` functions
+pub trait CheckedPadding<const BLOCK_SIZE_BYTES: usize>: Padding<BLOCK_SIZE_BYTES> {
+    /// Like `unpad` but fails instead of keeping the data when it doesn't end in valid padding
+    fn try_unpad(data: &[[u8; BLOCK_SIZE_BYTES]]) -> Result<Vec<u8>, PaddingError>;
+}
+
 fn bytes_needed_to_fit(data_len: usize, block_size: usize) -> usize {
     block_size - (data_len % block_size)
 }
@@ -51,16 +64,36 @@ where
         cypher_text
     }
     fn ecb_decrypt(key: &[u8; KEY_SIZE_BYTES], cypher_text: &[[u8; BLOCK_SIZE_BYTES]]) -> Vec<u8> {
-        let mut plain_text: Vec<[u8; BLOCK_SIZE_BYTES]> = Vec::with_capacity(cypher_text.len());
-        plain_text.extend(
-            cypher_text
-                .iter()
-                .map(|block| Self::decrypt_block(key, block)),
-        );
-        Self::unpad(&plain_text)
+        Self::unpad(&ecb_decrypt_blocks::<Self, BLOCK_SIZE_BYTES, KEY_SIZE_BYTES>(key, cypher_text))
+    }
+    /// Like `ecb_decrypt` but fails when the decrypted text doesn't end in valid padding
+    /// ## Errors
+    /// `PaddingError::BadPadding` if the padding is not valid
+    fn ecb_try_decrypt(
+        key: &[u8; KEY_SIZE_BYTES],
+        cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
+    ) -> Result<Vec<u8>, PaddingError>
+    where
+        Self: CheckedPadding<BLOCK_SIZE_BYTES>,
+    {
+        Self::try_unpad(
+            &ecb_decrypt_blocks::<Self, BLOCK_SIZE_BYTES, KEY_SIZE_BYTES>(key, cypher_text),
+        )
     }
 }
 
+fn ecb_decrypt_blocks<T, const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize>(
+    key: &[u8; KEY_SIZE_BYTES],
+    cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
+) -> Vec<[u8; BLOCK_SIZE_BYTES]>
+where
+    T: BlockCypher<BLOCK_SIZE_BYTES, KEY_SIZE_BYTES> + ?Sized,
+{
+    let mut plain_text: Vec<[u8; BLOCK_SIZE_BYTES]> = Vec::with_capacity(cypher_text.len());
+    plain_text.extend(cypher_text.iter().map(|block| T::decrypt_block(key, block)));
+    plain_text
+}
+
 impl<T, const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize>
     ECB<BLOCK_SIZE_BYTES, KEY_SIZE_BYTES> for T
 where
@@ -96,21 +129,45 @@ where
         cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
         iv: [u8; BLOCK_SIZE_BYTES],
     ) -> Vec<u8> {
-        let mut plain_text: Vec<[u8; BLOCK_SIZE_BYTES]> =
-            Vec::with_capacity(cypher_text.len() * BLOCK_SIZE_BYTES);
-        let with_iv = [&[iv], cypher_text].concat();
-        let iterator = with_iv.windows(2).map(|blocks| {
-            zip_with(
-                Self::decrypt_block(key, &blocks[1]),
-                blocks[0],
-                BitXor::bitxor,
-            )
-        });
-        plain_text.extend(iterator);
-        Self::unpad(&plain_text)
+        Self::unpad(
+            &cbc_decrypt_blocks::<Self, BLOCK_SIZE_BYTES, KEY_SIZE_BYTES>(key, cypher_text, iv),
+        )
+    }
+    /// Like `cbc_decrypt` but fails when the decrypted text doesn't end in valid padding
+    /// ## Errors
+    /// `PaddingError::BadPadding` if the padding is not valid
+    fn cbc_try_decrypt(
+        key: &[u8; KEY_SIZE_BYTES],
+        cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
+        iv: [u8; BLOCK_SIZE_BYTES],
+    ) -> Result<Vec<u8>, PaddingError>
+    where
+        Self: CheckedPadding<BLOCK_SIZE_BYTES>,
+    {
+        Self::try_unpad(
+            &cbc_decrypt_blocks::<Self, BLOCK_SIZE_BYTES, KEY_SIZE_BYTES>(key, cypher_text, iv),
+        )
     }
 }
 
+fn cbc_decrypt_blocks<T, const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize>(
+    key: &[u8; KEY_SIZE_BYTES],
+    cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
+    iv: [u8; BLOCK_SIZE_BYTES],
+) -> Vec<[u8; BLOCK_SIZE_BYTES]>
+where
+    T: BlockCypher<BLOCK_SIZE_BYTES, KEY_SIZE_BYTES> + ?Sized,
+{
+    let mut plain_text: Vec<[u8; BLOCK_SIZE_BYTES]> =
+        Vec::with_capacity(cypher_text.len() * BLOCK_SIZE_BYTES);
+    let with_iv = [&[iv], cypher_text].concat();
+    let iterator = with_iv
+        .windows(2)
+        .map(|blocks| zip_with(T::decrypt_block(key, &blocks[1]), blocks[0], BitXor::bitxor));
+    plain_text.extend(iterator);
+    plain_text
+}
+
 impl<T, const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize>
     CBC<BLOCK_SIZE_BYTES, KEY_SIZE_BYTES> for T
 where
@@ -146,16 +203,44 @@ where
         cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
         iv: [u8; BLOCK_SIZE_BYTES],
     ) -> Vec<u8> {
-        let mut plain_text: Vec<[u8; BLOCK_SIZE_BYTES]> = Vec::with_capacity(cypher_text.len());
-        let mut iv = iv;
-        let mut plain_block: [u8; BLOCK_SIZE_BYTES];
-        for block in cypher_text {
-            plain_block = zip_with(Self::decrypt_block(key, block), iv, BitXor::bitxor);
-            iv = zip_with(plain_block, *block, BitXor::bitxor);
-            plain_text.push(plain_block);
-        }
-        Self::unpad(&plain_text)
+        Self::unpad(
+            &pcbc_decrypt_blocks::<Self, BLOCK_SIZE_BYTES, KEY_SIZE_BYTES>(key, cypher_text, iv),
+        )
     }
+    /// Like `pcbc_decrypt` but fails when the decrypted text doesn't end in valid padding
+    /// ## Errors
+    /// `PaddingError::BadPadding` if the padding is not valid
+    fn pcbc_try_decrypt(
+        key: &[u8; KEY_SIZE_BYTES],
+        cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
+        iv: [u8; BLOCK_SIZE_BYTES],
+    ) -> Result<Vec<u8>, PaddingError>
+    where
+        Self: CheckedPadding<BLOCK_SIZE_BYTES>,
+    {
+        Self::try_unpad(
+            &pcbc_decrypt_blocks::<Self, BLOCK_SIZE_BYTES, KEY_SIZE_BYTES>(key, cypher_text, iv),
+        )
+    }
+}
+
+fn pcbc_decrypt_blocks<T, const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize>(
+    key: &[u8; KEY_SIZE_BYTES],
+    cypher_text: &[[u8; BLOCK_SIZE_BYTES]],
+    iv: [u8; BLOCK_SIZE_BYTES],
+) -> Vec<[u8; BLOCK_SIZE_BYTES]>
+where
+    T: BlockCypher<BLOCK_SIZE_BYTES, KEY_SIZE_BYTES> + ?Sized,
+{
+    let mut plain_text: Vec<[u8; BLOCK_SIZE_BYTES]> = Vec::with_capacity(cypher_text.len());
+    let mut iv = iv;
+    let mut plain_block: [u8; BLOCK_SIZE_BYTES];
+    for block in cypher_text {
+        plain_block = zip_with(T::decrypt_block(key, block), iv, BitXor::bitxor);
+        iv = zip_with(plain_block, *block, BitXor::bitxor);
+        plain_text.push(plain_block);
+    }
+    plain_text
 }
 
 impl<T, const BLOCK_SIZE_BYTES: usize, const KEY_SIZE_BYTES: usize>
@@ -198,7 +283,7 @@ where
         iv: [u8; BLOCK_SIZE_BYTES],
     ) -> Vec<u8> {
         let mut plain_text: Vec<u8> = Vec::with_capacity(cypher_text.len());
-        let block_iterator = cypher_text.array_chunks::<BLOCK_SIZE_BYTES>();
+        let mut block_iterator = cypher_text.array_chunks::<BLOCK_SIZE_BYTES>();
         let remainder = block_iterator.remainder();
         plain_text.extend(
             std::iter::once(&iv)
@@ -208,14 +293,14 @@ where
                 })
                 .flatten(),
         );
-        if let Some(last) = block_iterator.last() {
-            plain_text.extend(
-                Self::encrypt_block(key, last)
-                    .into_iter()
-                    .zip(remainder.iter())
-                    .map(|(a, b)| a ^ b),
-            );
-        }
+        // A cypher text shorter than a block is only xored with the encrypted iv
+        let last = block_iterator.next_back().unwrap_or(&iv);
+        plain_text.extend(
+            Self::encrypt_block(key, last)
+                .into_iter()
+                .zip(remainder.iter())
+                .map(|(a, b)| a ^ b),
+        );
         plain_text
     }
 }
@@ -1,6 +1,8 @@
 use ndarray::prelude::*;
 
-use crate::Block_cypher::{BlockCypher, Counter, Padding, CBC, CFB, CTR, ECB, OFB, PCBC};
+use crate::Block_cypher::{
+    BlockCypher, CheckedPadding, Counter, Padding, PaddingError, CBC, CFB, CTR, ECB, OFB, PCBC,
+};
 type AESState = Array2<u8>;
 
 #[derive(Debug, PartialEq)]
@@ -277,7 +279,7 @@ fn block_to_array(block: [u8; 16]) -> AESState {
     Array2::from_shape_vec((4, 4).f(), block.to_vec()).expect("Block should have been 16 bytes")
 }
 
-struct AES;
+pub(crate) struct AES;
 
 impl BlockCypher<16, 16> for AES {
     fn encrypt_block(key: &[u8; 16], plain_text_block: &[u8; 16]) -> [u8; 16] {
@@ -327,11 +329,12 @@ fn unpad_PKCS5<const AMOUNT: usize>(data: [u8; AMOUNT]) -> Result<Vec<u8>, [u8;
     assert_ne!(AMOUNT, 0);
     let mut output = data.to_vec();
     let added_bytes = data.last().expect("Cannot unpad empty data");
-    if data
-        .iter()
-        .rev()
-        .take(*added_bytes as usize)
-        .all(|x| *x == *added_bytes)
+    if (1..=AMOUNT).contains(&(*added_bytes as usize))
+        && data
+            .iter()
+            .rev()
+            .take(*added_bytes as usize)
+            .all(|x| *x == *added_bytes)
     {
         output.truncate(AMOUNT - *added_bytes as usize);
         Ok(output)
@@ -365,6 +368,20 @@ impl Padding<16> for AES {
     }
 }
 
+impl CheckedPadding<16> for AES {
+    /// PCKS#5 unpadding for the AES block cypher
+    /// ## Errors
+    /// `PaddingError::BadPadding` if data is empty or its last block isn't padded
+    fn try_unpad(data: &[[u8; 16]]) -> Result<Vec<u8>, PaddingError> {
+        let (last, xs) = data.split_last().ok_or(PaddingError::BadPadding)?;
+        let unpacked = unpad_PKCS5(*last).map_err(|_| PaddingError::BadPadding)?;
+        let mut buf: Vec<u8> = Vec::with_capacity(data.len() * 16);
+        buf.extend(xs.iter().flatten());
+        buf.extend(unpacked);
+        Ok(buf)
+    }
+}
+
 impl Counter<16> for AES {
     type Counter = u128;
     fn init_counter(init: [u8; 16]) -> Self::Counter {
@@ -531,6 +548,16 @@ fn PKCS5_padding_test() {
     assert_eq!(depadded_message, message)
 }
 
+#[test]
+fn PKCS5_bad_padding_test() {
+    // A zero byte isn't padding and a pad longer than the block would underflow the truncation
+    let mut zero_padded = [0x07; 16];
+    zero_padded[15] = 0x00;
+    assert_eq!(unpad_PKCS5(zero_padded), Err(zero_padded));
+    assert_eq!(unpad_PKCS5([0x11; 16]), Err([0x11; 16]));
+    assert_eq!(unpad_PKCS5([0x10; 16]), Ok(Vec::new()));
+}
+
 #[test]
 fn ecb_encryption_decryption_test() {
     let message = ENCRYPTION_TEST;
@@ -596,6 +623,20 @@ fn cfb_encrypt_decrypt_test() {
     assert_eq!(decrypted_message, message.to_vec());
 }
 
+#[test]
+fn cfb_short_cypher_text_test() {
+    // Less than a block is only xored with the encrypted iv, so it is a prefix of the long answer
+    let key: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    let message = &ENCRYPTION_TEST[..5];
+    let encrypted_message = AES::cfb_encrypt(&key, message, [0x01; 16]);
+    assert_eq!(encrypted_message, CFB_ANSWER[..5]);
+    let decrypted_message = AES::cfb_decrypt(&key, &CFB_ANSWER[..5], [0x01; 16]);
+    assert_eq!(decrypted_message, message);
+}
+
 #[test]
 fn ofb_encrypt_decrypt_test() {
     let message = ENCRYPTION_TEST;
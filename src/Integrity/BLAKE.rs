@@ -1,3 +1,9 @@
+pub mod BLAKE3;
+
+use crate::Integrity::Digest;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
 const SIGMA: [[usize; 16]; 10] = [
     [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
     [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
@@ -41,7 +47,13 @@ fn mix_2b(work_vector: &mut [u64], a: usize, b: usize, c: usize, d: usize, x: u6
     work_vector[b] = (work_vector[b] ^ work_vector[c]).rotate_right(63);
 }
 
-fn compress_2b(state: &mut [u64; 8], chunk: &[u8; 128], offset: u128, is_last: bool) {
+fn compress_2b(
+    state: &mut [u64; 8],
+    chunk: &[u8; 128],
+    offset: u128,
+    is_last: bool,
+    is_last_node: bool,
+) {
     // Init work and message vectors
     let mut work_vector: Vec<u64> = [*state, INITIALIZATION_VECTOR_2B].concat();
     let message_chunk: [u64; 16] = chunk
@@ -57,6 +69,9 @@ fn compress_2b(state: &mut [u64; 8], chunk: &[u8; 128], offset: u128, is_last: b
     if is_last {
         work_vector[14] = !work_vector[14]
     }
+    if is_last_node {
+        work_vector[15] = !work_vector[15]
+    }
 
     let mut round_sigma: [usize; 16];
     // Throw them into the cryptographic blender
@@ -142,65 +157,331 @@ fn compress_2b(state: &mut [u64; 8], chunk: &[u8; 128], offset: u128, is_last: b
     }
 }
 
+/// Parameter block of BLAKE2B. Gives the default parameters for a 64 byte unkeyed digest with `new`,
+/// the other fields can be set with the builder methods. See section 2.5 of the [BLAKE2 paper](https://www.blake2.net/blake2.pdf)
+#[derive(Clone)]
+pub struct Blake2bParams {
+    hash_len: u8,
+    key: Zeroizing<Vec<u8>>,
+    fanout: u8,
+    max_depth: u8,
+    max_leaf_len: u32,
+    node_offset: u64,
+    node_depth: u8,
+    inner_hash_len: u8,
+    xof_len: u32,
+    salt: [u8; 16],
+    personalization: [u8; 16],
+    last_node: bool,
+}
+
+impl Blake2bParams {
+    pub fn new() -> Blake2bParams {
+        Blake2bParams {
+            hash_len: 64,
+            key: Zeroizing::new(Vec::new()),
+            fanout: 1,
+            max_depth: 1,
+            max_leaf_len: 0,
+            node_offset: 0,
+            node_depth: 0,
+            inner_hash_len: 0,
+            xof_len: 0,
+            salt: [0x00; 16],
+            personalization: [0x00; 16],
+            last_node: false,
+        }
+    }
+
+    /// ## Panics
+    /// if `hash_len == 0` or `hash_len > 64`
+    pub fn hash_length(mut self, hash_len: usize) -> Self {
+        assert!(hash_len <= 64 && 0 < hash_len);
+        self.hash_len = hash_len as u8;
+        self
+    }
+
+    /// ## Panics
+    /// if `key.len() > 64`
+    pub fn key(mut self, key: &[u8]) -> Self {
+        assert!(key.len() <= 64);
+        self.key = Zeroizing::new(key.to_vec());
+        self
+    }
+
+    /// Salt is zero padded to 16 bytes
+    /// ## Panics
+    /// if `salt.len() > 16`
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        assert!(salt.len() <= 16);
+        self.salt = [0x00; 16];
+        self.salt[..salt.len()].copy_from_slice(salt);
+        self
+    }
+
+    /// Personalization is zero padded to 16 bytes
+    /// ## Panics
+    /// if `personalization.len() > 16`
+    pub fn personalization(mut self, personalization: &[u8]) -> Self {
+        assert!(personalization.len() <= 16);
+        self.personalization = [0x00; 16];
+        self.personalization[..personalization.len()].copy_from_slice(personalization);
+        self
+    }
+
+    /// Fanout of the tree, 0 means unlimited
+    pub fn fanout(mut self, fanout: u8) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Maximal depth of the tree, 255 means unlimited
+    /// ## Panics
+    /// if `max_depth == 0`
+    pub fn max_depth(mut self, max_depth: u8) -> Self {
+        assert_ne!(max_depth, 0);
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Maximal byte length of a leaf, 0 means unlimited
+    pub fn max_leaf_length(mut self, max_leaf_len: u32) -> Self {
+        self.max_leaf_len = max_leaf_len;
+        self
+    }
+
+    pub fn node_offset(mut self, node_offset: u64) -> Self {
+        self.node_offset = node_offset;
+        self
+    }
+
+    /// Depth of the node, 0 for leaves
+    pub fn node_depth(mut self, node_depth: u8) -> Self {
+        self.node_depth = node_depth;
+        self
+    }
+
+    /// Digest length of the inner nodes in tree hashing
+    /// ## Panics
+    /// if `inner_hash_len > 64`
+    pub fn inner_hash_length(mut self, inner_hash_len: usize) -> Self {
+        assert!(inner_hash_len <= 64);
+        self.inner_hash_len = inner_hash_len as u8;
+        self
+    }
+
+    /// Output length of BLAKE2XB. It shares the parameter block with the upper half of the node offset
+    pub fn xof_length(mut self, xof_len: u32) -> Self {
+        self.xof_len = xof_len;
+        self
+    }
+
+    /// Whether this is the last node on its level in tree hashing
+    pub fn last_node(mut self, last_node: bool) -> Self {
+        self.last_node = last_node;
+        self
+    }
+
+    fn parameter_block(&self) -> [u8; 64] {
+        let mut block = [0x00; 64];
+        block[0] = self.hash_len;
+        block[1] = self.key.len() as u8;
+        block[2] = self.fanout;
+        block[3] = self.max_depth;
+        block[4..8].copy_from_slice(&self.max_leaf_len.to_le_bytes());
+        block[8..16]
+            .copy_from_slice(&(self.node_offset ^ (u64::from(self.xof_len) << 32)).to_le_bytes());
+        block[16] = self.node_depth;
+        block[17] = self.inner_hash_len;
+        block[32..48].copy_from_slice(&self.salt);
+        block[48..64].copy_from_slice(&self.personalization);
+        block
+    }
+
+    pub fn to_state(&self) -> Blake2bState {
+        Blake2bState::new(self)
+    }
+}
+
+impl Default for Blake2bParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Leaves out the key
+impl fmt::Debug for Blake2bParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blake2bParams")
+            .field("hash_len", &self.hash_len)
+            .field("key_len", &self.key.len())
+            .field("fanout", &self.fanout)
+            .field("max_depth", &self.max_depth)
+            .field("max_leaf_len", &self.max_leaf_len)
+            .field("node_offset", &self.node_offset)
+            .field("node_depth", &self.node_depth)
+            .field("inner_hash_len", &self.inner_hash_len)
+            .field("xof_len", &self.xof_len)
+            .field("salt", &self.salt)
+            .field("personalization", &self.personalization)
+            .field("last_node", &self.last_node)
+            .finish()
+    }
+}
+
+/// Incremental BLAKE2B hasher made from `Blake2bParams`. The buffer can hold the key block, so it is
+/// left out of `Debug` and wiped on drop
+#[derive(Clone)]
+pub struct Blake2bState {
+    state: [u64; 8],
+    buffer: [u8; 128],
+    buffer_len: usize,
+    bytes_compressed: u128,
+    hash_len: usize,
+    last_node: bool,
+}
+
+impl Blake2bState {
+    pub fn new(params: &Blake2bParams) -> Blake2bState {
+        let mut state: [u64; 8] = INITIALIZATION_VECTOR_2B;
+        for (word, param) in state.iter_mut().zip(params.parameter_block().chunks(8)) {
+            *word ^= u64::from_le_bytes(param.try_into().unwrap());
+        }
+        let mut blake_state = Blake2bState {
+            state,
+            buffer: [0x00; 128],
+            buffer_len: 0,
+            bytes_compressed: 0,
+            hash_len: params.hash_len as usize,
+            last_node: params.last_node,
+        };
+        // bake the key in to the message if there is one
+        if !params.key.is_empty() {
+            blake_state.buffer[..params.key.len()].copy_from_slice(&params.key);
+            blake_state.buffer_len = 128;
+        }
+        blake_state
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        let mut input = input;
+        while !input.is_empty() {
+            // The last chunk has to be kept for finalize, so only compress when there is more input
+            if self.buffer_len == 128 {
+                self.bytes_compressed += 128;
+                compress_2b(
+                    &mut self.state,
+                    &self.buffer,
+                    self.bytes_compressed,
+                    false,
+                    false,
+                );
+                self.buffer_len = 0;
+            }
+            let amount = (128 - self.buffer_len).min(input.len());
+            self.buffer[self.buffer_len..self.buffer_len + amount]
+                .copy_from_slice(&input[..amount]);
+            self.buffer_len += amount;
+            input = &input[amount..];
+        }
+    }
+
+    /// Returns the digest, which is as long as the hash length in the parameters
+    pub fn finalize(mut self) -> Vec<u8> {
+        self.bytes_compressed += self.buffer_len as u128;
+        self.buffer[self.buffer_len..].fill(0x00);
+        compress_2b(
+            &mut self.state,
+            &self.buffer,
+            self.bytes_compressed,
+            true,
+            self.last_node,
+        );
+        self.state
+            .into_iter()
+            .flat_map(|word| word.to_le_bytes())
+            .take(self.hash_len)
+            .collect()
+    }
+}
+
+impl fmt::Debug for Blake2bState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blake2bState")
+            .field("buffer_len", &self.buffer_len)
+            .field("bytes_compressed", &self.bytes_compressed)
+            .field("hash_len", &self.hash_len)
+            .field("last_node", &self.last_node)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Blake2bState {
+    fn drop(&mut self) {
+        self.state.zeroize();
+        self.buffer.zeroize();
+    }
+}
+
 /// The BLAKE2B hash function. HASH_LEN is the digest length in bytes and is between 1 and 64.<br>Key is optional and if there is one, it should be less then 64 bytes.
 ///
 /// ## Panics
 /// if `HASH_LEN == 0` or `HASH_LEN > 64`<br>
 /// if `key.unwrap_or(vec![]).len() > 64`
 pub fn blake2b<const HASH_LEN: usize>(input: &[u8], key: Option<Vec<u8>>) -> [u8; HASH_LEN] {
-    assert!(HASH_LEN <= 64 && 0 < HASH_LEN);
-    // Take the key
-    let key: Vec<u8> = key.unwrap_or_default();
-    assert!(key.len() <= 64);
-    // Init the state
-    let mut state: [u64; 8] = INITIALIZATION_VECTOR_2B;
-    state[0] ^= 0x01010000 ^ ((key.len() as u64) << 8) ^ HASH_LEN as u64;
-
-    let mut bytes_compressed: u128 = 0;
-    let mut bytes_remaning: u128 = input.len() as u128;
-
-    // bake the key in to the message if there is one
-    let mut M = input.to_vec();
-    if !key.is_empty() {
-        M = [pad_with_zeros(&key, 128), M].concat();
-        bytes_remaning += 128;
-    }
-
-    // start compressing
-    let mut chunks = M.chunks(128);
-    let mut chunk: [u8; 128];
-    while bytes_remaning > 128 {
-        chunk = chunks
-            .next()
-            .expect("There was no next element")
-            .try_into()
-            .unwrap();
-        bytes_compressed += 128;
-        bytes_remaning -= 128;
-        compress_2b(&mut state, &chunk, bytes_compressed, false);
-    }
-
-    // compress the last chunk
-    chunk = pad_with_zeros(chunks.next().unwrap_or(&[]), 128)
-        .try_into()
-        .expect("Padding did not work");
-    bytes_compressed += bytes_remaning;
-    compress_2b(&mut state, &chunk, bytes_compressed, true);
-
-    // Return the first HASH_LEN bytes of the state
-    state
-        .into_iter()
-        .flat_map(|word| word.to_le_bytes())
-        .take(HASH_LEN)
-        .collect::<Vec<u8>>()
-        .try_into()
-        .unwrap()
+    let mut state = Blake2bParams::new()
+        .hash_length(HASH_LEN)
+        .key(&key.unwrap_or_default())
+        .to_state();
+    state.update(input);
+    state.finalize().try_into().unwrap()
 }
 
-fn pad_with_zeros(input: &[u8], size: u32) -> Vec<u8> {
-    match size as usize > input.len() {
-        true => [input, &[0x00].repeat(size as usize - input.len())].concat(),
-        false => input.to_vec(),
+/// BLAKE2B without a key and with a fixed digest length of HASH_LEN bytes, so it can be used as a `Digest`
+#[derive(Debug, Clone)]
+pub struct Blake2b<const HASH_LEN: usize> {
+    state: Blake2bState,
+}
+
+impl<const HASH_LEN: usize> Blake2b<HASH_LEN> {
+    /// ## Panics
+    /// if `HASH_LEN == 0` or `HASH_LEN > 64`
+    pub fn new() -> Self {
+        Blake2b {
+            state: Blake2bParams::new().hash_length(HASH_LEN).to_state(),
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.state.update(input)
+    }
+
+    pub fn finalize(self) -> [u8; HASH_LEN] {
+        self.state.finalize().try_into().unwrap()
+    }
+}
+
+impl<const HASH_LEN: usize> Default for Blake2b<HASH_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const HASH_LEN: usize> Digest for Blake2b<HASH_LEN> {
+    const OUTPUT_SIZE: usize = HASH_LEN;
+    const BLOCK_SIZE: usize = 128;
+    type Output = [u8; HASH_LEN];
+
+    fn new() -> Self {
+        Blake2b::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Blake2b::update(self, input)
+    }
+
+    fn finalize(self) -> Self::Output {
+        Blake2b::finalize(self)
     }
 }
 
@@ -221,7 +502,13 @@ fn mix_2s(work_vector: &mut [u32], a: usize, b: usize, c: usize, d: usize, x: u3
     work_vector[b] = (work_vector[b] ^ work_vector[c]).rotate_right(7);
 }
 
-fn compress_2s(state: &mut [u32; 8], chunk: &[u8; 64], offset: u64, is_last: bool) {
+fn compress_2s(
+    state: &mut [u32; 8],
+    chunk: &[u8; 64],
+    offset: u64,
+    is_last: bool,
+    is_last_node: bool,
+) {
     // Init work and message vectors
     let mut work_vector: Vec<u32> = [*state, INITIALIZATION_VECTOR_2S].concat();
     let message_chunk: [u32; 16] = chunk
@@ -237,6 +524,9 @@ fn compress_2s(state: &mut [u32; 8], chunk: &[u8; 64], offset: u64, is_last: boo
     if is_last {
         work_vector[14] = !work_vector[14]
     }
+    if is_last_node {
+        work_vector[15] = !work_vector[15]
+    }
 
     let mut round_sigma: [usize; 16];
     // Throw them into the cryptographic blender
@@ -322,59 +612,336 @@ fn compress_2s(state: &mut [u32; 8], chunk: &[u8; 64], offset: u64, is_last: boo
     }
 }
 
+/// Parameter block of BLAKE2S. Gives the default parameters for a 32 byte unkeyed digest with `new`,
+/// the other fields can be set with the builder methods. See section 2.5 of the [BLAKE2 paper](https://www.blake2.net/blake2.pdf)
+#[derive(Clone)]
+pub struct Blake2sParams {
+    hash_len: u8,
+    key: Zeroizing<Vec<u8>>,
+    fanout: u8,
+    max_depth: u8,
+    max_leaf_len: u32,
+    node_offset: u64,
+    node_depth: u8,
+    inner_hash_len: u8,
+    xof_len: u16,
+    salt: [u8; 8],
+    personalization: [u8; 8],
+    last_node: bool,
+}
+
+impl Blake2sParams {
+    pub fn new() -> Blake2sParams {
+        Blake2sParams {
+            hash_len: 32,
+            key: Zeroizing::new(Vec::new()),
+            fanout: 1,
+            max_depth: 1,
+            max_leaf_len: 0,
+            node_offset: 0,
+            node_depth: 0,
+            inner_hash_len: 0,
+            xof_len: 0,
+            salt: [0x00; 8],
+            personalization: [0x00; 8],
+            last_node: false,
+        }
+    }
+
+    /// ## Panics
+    /// if `hash_len == 0` or `hash_len > 32`
+    pub fn hash_length(mut self, hash_len: usize) -> Self {
+        assert!(hash_len <= 32 && 0 < hash_len);
+        self.hash_len = hash_len as u8;
+        self
+    }
+
+    /// ## Panics
+    /// if `key.len() > 32`
+    pub fn key(mut self, key: &[u8]) -> Self {
+        assert!(key.len() <= 32);
+        self.key = Zeroizing::new(key.to_vec());
+        self
+    }
+
+    /// Salt is zero padded to 8 bytes
+    /// ## Panics
+    /// if `salt.len() > 8`
+    pub fn salt(mut self, salt: &[u8]) -> Self {
+        assert!(salt.len() <= 8);
+        self.salt = [0x00; 8];
+        self.salt[..salt.len()].copy_from_slice(salt);
+        self
+    }
+
+    /// Personalization is zero padded to 8 bytes
+    /// ## Panics
+    /// if `personalization.len() > 8`
+    pub fn personalization(mut self, personalization: &[u8]) -> Self {
+        assert!(personalization.len() <= 8);
+        self.personalization = [0x00; 8];
+        self.personalization[..personalization.len()].copy_from_slice(personalization);
+        self
+    }
+
+    /// Fanout of the tree, 0 means unlimited
+    pub fn fanout(mut self, fanout: u8) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Maximal depth of the tree, 255 means unlimited
+    /// ## Panics
+    /// if `max_depth == 0`
+    pub fn max_depth(mut self, max_depth: u8) -> Self {
+        assert_ne!(max_depth, 0);
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Maximal byte length of a leaf, 0 means unlimited
+    pub fn max_leaf_length(mut self, max_leaf_len: u32) -> Self {
+        self.max_leaf_len = max_leaf_len;
+        self
+    }
+
+    /// ## Panics
+    /// if `node_offset >= 2^48`
+    pub fn node_offset(mut self, node_offset: u64) -> Self {
+        assert!(node_offset < 1 << 48);
+        self.node_offset = node_offset;
+        self
+    }
+
+    /// Depth of the node, 0 for leaves
+    pub fn node_depth(mut self, node_depth: u8) -> Self {
+        self.node_depth = node_depth;
+        self
+    }
+
+    /// Digest length of the inner nodes in tree hashing
+    /// ## Panics
+    /// if `inner_hash_len > 32`
+    pub fn inner_hash_length(mut self, inner_hash_len: usize) -> Self {
+        assert!(inner_hash_len <= 32);
+        self.inner_hash_len = inner_hash_len as u8;
+        self
+    }
+
+    /// Output length of BLAKE2XS. It shares the parameter block with the upper two bytes of the node offset
+    pub fn xof_length(mut self, xof_len: u16) -> Self {
+        self.xof_len = xof_len;
+        self
+    }
+
+    /// Whether this is the last node on its level in tree hashing
+    pub fn last_node(mut self, last_node: bool) -> Self {
+        self.last_node = last_node;
+        self
+    }
+
+    fn parameter_block(&self) -> [u8; 32] {
+        let mut block = [0x00; 32];
+        block[0] = self.hash_len;
+        block[1] = self.key.len() as u8;
+        block[2] = self.fanout;
+        block[3] = self.max_depth;
+        block[4..8].copy_from_slice(&self.max_leaf_len.to_le_bytes());
+        block[8..14].copy_from_slice(
+            &(self.node_offset ^ (u64::from(self.xof_len) << 32)).to_le_bytes()[..6],
+        );
+        block[14] = self.node_depth;
+        block[15] = self.inner_hash_len;
+        block[16..24].copy_from_slice(&self.salt);
+        block[24..32].copy_from_slice(&self.personalization);
+        block
+    }
+
+    pub fn to_state(&self) -> Blake2sState {
+        Blake2sState::new(self)
+    }
+}
+
+impl Default for Blake2sParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Leaves out the key
+impl fmt::Debug for Blake2sParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blake2sParams")
+            .field("hash_len", &self.hash_len)
+            .field("key_len", &self.key.len())
+            .field("fanout", &self.fanout)
+            .field("max_depth", &self.max_depth)
+            .field("max_leaf_len", &self.max_leaf_len)
+            .field("node_offset", &self.node_offset)
+            .field("node_depth", &self.node_depth)
+            .field("inner_hash_len", &self.inner_hash_len)
+            .field("xof_len", &self.xof_len)
+            .field("salt", &self.salt)
+            .field("personalization", &self.personalization)
+            .field("last_node", &self.last_node)
+            .finish()
+    }
+}
+
+/// Incremental BLAKE2S hasher made from `Blake2sParams`. The buffer can hold the key block, so it is
+/// left out of `Debug` and wiped on drop
+#[derive(Clone)]
+pub struct Blake2sState {
+    state: [u32; 8],
+    buffer: [u8; 64],
+    buffer_len: usize,
+    bytes_compressed: u64,
+    hash_len: usize,
+    last_node: bool,
+}
+
+impl Blake2sState {
+    pub fn new(params: &Blake2sParams) -> Blake2sState {
+        let mut state: [u32; 8] = INITIALIZATION_VECTOR_2S;
+        for (word, param) in state.iter_mut().zip(params.parameter_block().chunks(4)) {
+            *word ^= u32::from_le_bytes(param.try_into().unwrap());
+        }
+        let mut blake_state = Blake2sState {
+            state,
+            buffer: [0x00; 64],
+            buffer_len: 0,
+            bytes_compressed: 0,
+            hash_len: params.hash_len as usize,
+            last_node: params.last_node,
+        };
+        // bake the key in to the message if there is one
+        if !params.key.is_empty() {
+            blake_state.buffer[..params.key.len()].copy_from_slice(&params.key);
+            blake_state.buffer_len = 64;
+        }
+        blake_state
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        let mut input = input;
+        while !input.is_empty() {
+            // The last chunk has to be kept for finalize, so only compress when there is more input
+            if self.buffer_len == 64 {
+                self.bytes_compressed += 64;
+                compress_2s(
+                    &mut self.state,
+                    &self.buffer,
+                    self.bytes_compressed,
+                    false,
+                    false,
+                );
+                self.buffer_len = 0;
+            }
+            let amount = (64 - self.buffer_len).min(input.len());
+            self.buffer[self.buffer_len..self.buffer_len + amount]
+                .copy_from_slice(&input[..amount]);
+            self.buffer_len += amount;
+            input = &input[amount..];
+        }
+    }
+
+    /// Returns the digest, which is as long as the hash length in the parameters
+    pub fn finalize(mut self) -> Vec<u8> {
+        self.bytes_compressed += self.buffer_len as u64;
+        self.buffer[self.buffer_len..].fill(0x00);
+        compress_2s(
+            &mut self.state,
+            &self.buffer,
+            self.bytes_compressed,
+            true,
+            self.last_node,
+        );
+        self.state
+            .into_iter()
+            .flat_map(|word| word.to_le_bytes())
+            .take(self.hash_len)
+            .collect()
+    }
+}
+
+impl fmt::Debug for Blake2sState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blake2sState")
+            .field("buffer_len", &self.buffer_len)
+            .field("bytes_compressed", &self.bytes_compressed)
+            .field("hash_len", &self.hash_len)
+            .field("last_node", &self.last_node)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Blake2sState {
+    fn drop(&mut self) {
+        self.state.zeroize();
+        self.buffer.zeroize();
+    }
+}
+
 /// The BLAKE2S hash function. HASH_LEN is the digest length in bytes and is between 1 and 32.<br>Key is optional and if there is one, it should be less then 32 bytes.
 ///
 /// ## Panics
 /// if `HASH_LEN == 0` or `HASH_LEN > 32`<br>
 /// if `key.unwrap_or(vec![]).len() > 32`
 pub fn blake2s<const HASH_LEN: usize>(input: &[u8], key: Option<Vec<u8>>) -> [u8; HASH_LEN] {
-    assert!(HASH_LEN <= 32 && 0 < HASH_LEN);
-    // Take the key
-    let key: Vec<u8> = key.unwrap_or_default();
-    assert!(key.len() <= 32);
-    // Init the state
-    let mut state: [u32; 8] = INITIALIZATION_VECTOR_2S;
-    state[0] ^= 0x01010000 ^ ((key.len() as u32) << 8) ^ HASH_LEN as u32;
-
-    let mut bytes_compressed: u64 = 0;
-    let mut bytes_remaning: u64 = input.len() as u64;
-
-    // bake the key in to the message if there is one
-    let mut M = input.to_vec();
-    if !key.is_empty() {
-        M = [pad_with_zeros(&key, 64), M].concat();
-        bytes_remaning += 64;
-    }
-
-    // start compressing
-    let mut chunks = M.chunks(64);
-    let mut chunk: [u8; 64];
-    while bytes_remaning > 64 {
-        chunk = chunks
-            .next()
-            .expect("There was no next element")
-            .try_into()
-            .unwrap();
-        bytes_compressed += 64;
-        bytes_remaning -= 64;
-        compress_2s(&mut state, &chunk, bytes_compressed, false);
-    }
-
-    // compress the last chunk
-    chunk = pad_with_zeros(chunks.next().unwrap_or(&[]), 64)
-        .try_into()
-        .expect("Padding did not work");
-    bytes_compressed += bytes_remaning;
-    compress_2s(&mut state, &chunk, bytes_compressed, true);
-
-    // Return the first HASH_LEN bytes of the state
-    state
-        .into_iter()
-        .flat_map(|word| word.to_le_bytes())
-        .take(HASH_LEN)
-        .collect::<Vec<u8>>()
-        .try_into()
-        .unwrap()
+    let mut state = Blake2sParams::new()
+        .hash_length(HASH_LEN)
+        .key(&key.unwrap_or_default())
+        .to_state();
+    state.update(input);
+    state.finalize().try_into().unwrap()
+}
+
+/// BLAKE2S without a key and with a fixed digest length of HASH_LEN bytes, so it can be used as a `Digest`
+#[derive(Debug, Clone)]
+pub struct Blake2s<const HASH_LEN: usize> {
+    state: Blake2sState,
+}
+
+impl<const HASH_LEN: usize> Blake2s<HASH_LEN> {
+    /// ## Panics
+    /// if `HASH_LEN == 0` or `HASH_LEN > 32`
+    pub fn new() -> Self {
+        Blake2s {
+            state: Blake2sParams::new().hash_length(HASH_LEN).to_state(),
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.state.update(input)
+    }
+
+    pub fn finalize(self) -> [u8; HASH_LEN] {
+        self.state.finalize().try_into().unwrap()
+    }
+}
+
+impl<const HASH_LEN: usize> Default for Blake2s<HASH_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const HASH_LEN: usize> Digest for Blake2s<HASH_LEN> {
+    const OUTPUT_SIZE: usize = HASH_LEN;
+    const BLOCK_SIZE: usize = 64;
+    type Output = [u8; HASH_LEN];
+
+    fn new() -> Self {
+        Blake2s::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Blake2s::update(self, input)
+    }
+
+    fn finalize(self) -> Self::Output {
+        Blake2s::finalize(self)
+    }
 }
 
 pub fn blake2_244(input: &[u8]) -> [u8; 28] {
@@ -393,6 +960,422 @@ pub fn blake2_512(input: &[u8]) -> [u8; 64] {
     blake2b::<64>(input, None)
 }
 
+/// Inputs at least this long are hashed with a thread per leaf in `blake2bp` and `blake2sp`
+const PARALLEL_THRESHOLD: usize = 1 << 16;
+
+fn blake2bp_leaf(hash_len: usize, key: &[u8], node_offset: u64) -> Blake2bState {
+    let mut leaf = Blake2bParams::new()
+        .hash_length(hash_len)
+        .key(key)
+        .fanout(4)
+        .max_depth(2)
+        .node_offset(node_offset)
+        .inner_hash_length(64)
+        .last_node(node_offset == 3)
+        .to_state();
+    // Leaves always give the full inner hash to the root
+    leaf.hash_len = 64;
+    leaf
+}
+
+fn blake2bp_root(hash_len: usize, key: &[u8]) -> Blake2bState {
+    let mut root = Blake2bParams::new()
+        .hash_length(hash_len)
+        .key(key)
+        .fanout(4)
+        .max_depth(2)
+        .node_depth(1)
+        .inner_hash_length(64)
+        .last_node(true)
+        .to_state();
+    // The key length is in the parameters of the root, but only the leaves absorb the key
+    root.buffer_len = 0;
+    root
+}
+
+/// Incremental BLAKE2BP hasher. The input is split into 128 byte blocks, which are given to four
+/// BLAKE2B leaves in turns, and the root hashes the digests of the leaves.
+#[derive(Debug, Clone)]
+pub struct Blake2bpState {
+    leaves: [Blake2bState; 4],
+    root: Blake2bState,
+    bytes_absorbed: u128,
+}
+
+impl Blake2bpState {
+    /// ## Panics
+    /// if `hash_len == 0` or `hash_len > 64`<br>
+    /// if `key.len() > 64`
+    pub fn new(hash_len: usize, key: &[u8]) -> Blake2bpState {
+        Blake2bpState {
+            leaves: [0, 1, 2, 3].map(|node_offset| blake2bp_leaf(hash_len, key, node_offset)),
+            root: blake2bp_root(hash_len, key),
+            bytes_absorbed: 0,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        let mut input = input;
+        while !input.is_empty() {
+            let leaf = ((self.bytes_absorbed / 128) % 4) as usize;
+            let amount = (128 - (self.bytes_absorbed % 128) as usize).min(input.len());
+            self.leaves[leaf].update(&input[..amount]);
+            self.bytes_absorbed += amount as u128;
+            input = &input[amount..];
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut root = self.root;
+        for leaf in self.leaves {
+            root.update(&leaf.finalize());
+        }
+        root.finalize()
+    }
+}
+
+/// The BLAKE2BP hash function, the 4-way parallel version of BLAKE2B. Inputs of 64KiB or more are hashed with a thread per leaf.
+///
+/// ## Panics
+/// if `HASH_LEN == 0` or `HASH_LEN > 64`<br>
+/// if `key.unwrap_or(vec![]).len() > 64`
+pub fn blake2bp<const HASH_LEN: usize>(input: &[u8], key: Option<Vec<u8>>) -> [u8; HASH_LEN] {
+    let key: Vec<u8> = key.unwrap_or_default();
+    let hash_leaf = |node_offset: u64| {
+        let mut leaf = blake2bp_leaf(HASH_LEN, &key, node_offset);
+        for block in input.chunks(128).skip(node_offset as usize).step_by(4) {
+            leaf.update(block);
+        }
+        leaf.finalize()
+    };
+    let leaf_hashes: Vec<Vec<u8>> = if input.len() >= PARALLEL_THRESHOLD {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|node_offset| scope.spawn(move || hash_leaf(node_offset)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Leaf thread panicked"))
+                .collect()
+        })
+    } else {
+        (0..4).map(hash_leaf).collect()
+    };
+    let mut root = blake2bp_root(HASH_LEN, &key);
+    for leaf_hash in leaf_hashes {
+        root.update(&leaf_hash);
+    }
+    root.finalize().try_into().unwrap()
+}
+
+fn blake2sp_leaf(hash_len: usize, key: &[u8], node_offset: u64) -> Blake2sState {
+    let mut leaf = Blake2sParams::new()
+        .hash_length(hash_len)
+        .key(key)
+        .fanout(8)
+        .max_depth(2)
+        .node_offset(node_offset)
+        .inner_hash_length(32)
+        .last_node(node_offset == 7)
+        .to_state();
+    // Leaves always give the full inner hash to the root
+    leaf.hash_len = 32;
+    leaf
+}
+
+fn blake2sp_root(hash_len: usize, key: &[u8]) -> Blake2sState {
+    let mut root = Blake2sParams::new()
+        .hash_length(hash_len)
+        .key(key)
+        .fanout(8)
+        .max_depth(2)
+        .node_depth(1)
+        .inner_hash_length(32)
+        .last_node(true)
+        .to_state();
+    // The key length is in the parameters of the root, but only the leaves absorb the key
+    root.buffer_len = 0;
+    root
+}
+
+/// Incremental BLAKE2SP hasher. The input is split into 64 byte blocks, which are given to eight
+/// BLAKE2S leaves in turns, and the root hashes the digests of the leaves.
+#[derive(Debug, Clone)]
+pub struct Blake2spState {
+    leaves: [Blake2sState; 8],
+    root: Blake2sState,
+    bytes_absorbed: u128,
+}
+
+impl Blake2spState {
+    /// ## Panics
+    /// if `hash_len == 0` or `hash_len > 32`<br>
+    /// if `key.len() > 32`
+    pub fn new(hash_len: usize, key: &[u8]) -> Blake2spState {
+        Blake2spState {
+            leaves: [0, 1, 2, 3, 4, 5, 6, 7]
+                .map(|node_offset| blake2sp_leaf(hash_len, key, node_offset)),
+            root: blake2sp_root(hash_len, key),
+            bytes_absorbed: 0,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        let mut input = input;
+        while !input.is_empty() {
+            let leaf = ((self.bytes_absorbed / 64) % 8) as usize;
+            let amount = (64 - (self.bytes_absorbed % 64) as usize).min(input.len());
+            self.leaves[leaf].update(&input[..amount]);
+            self.bytes_absorbed += amount as u128;
+            input = &input[amount..];
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut root = self.root;
+        for leaf in self.leaves {
+            root.update(&leaf.finalize());
+        }
+        root.finalize()
+    }
+}
+
+/// The BLAKE2SP hash function, the 8-way parallel version of BLAKE2S. Inputs of 64KiB or more are hashed with a thread per leaf.
+///
+/// ## Panics
+/// if `HASH_LEN == 0` or `HASH_LEN > 32`<br>
+/// if `key.unwrap_or(vec![]).len() > 32`
+pub fn blake2sp<const HASH_LEN: usize>(input: &[u8], key: Option<Vec<u8>>) -> [u8; HASH_LEN] {
+    let key: Vec<u8> = key.unwrap_or_default();
+    let hash_leaf = |node_offset: u64| {
+        let mut leaf = blake2sp_leaf(HASH_LEN, &key, node_offset);
+        for block in input.chunks(64).skip(node_offset as usize).step_by(8) {
+            leaf.update(block);
+        }
+        leaf.finalize()
+    };
+    let leaf_hashes: Vec<Vec<u8>> = if input.len() >= PARALLEL_THRESHOLD {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|node_offset| scope.spawn(move || hash_leaf(node_offset)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Leaf thread panicked"))
+                .collect()
+        })
+    } else {
+        (0..8).map(hash_leaf).collect()
+    };
+    let mut root = blake2sp_root(HASH_LEN, &key);
+    for leaf_hash in leaf_hashes {
+        root.update(&leaf_hash);
+    }
+    root.finalize().try_into().unwrap()
+}
+
+/// Incremental BLAKE2XB extendable output function. The key, salt and personalization are taken
+/// from the given parameters and BLAKE2X sets the rest of the parameter block.
+#[derive(Debug, Clone)]
+pub struct Blake2xbState {
+    root: Blake2bState,
+    params: Blake2bParams,
+}
+
+impl Blake2xbState {
+    /// ## Panics
+    /// if `output_len == 0`
+    pub fn new(output_len: u32, params: &Blake2bParams) -> Blake2xbState {
+        assert_ne!(output_len, 0);
+        let params = Blake2bParams {
+            hash_len: 64,
+            fanout: 1,
+            max_depth: 1,
+            max_leaf_len: 0,
+            node_offset: 0,
+            node_depth: 0,
+            inner_hash_len: 0,
+            xof_len: output_len,
+            last_node: false,
+            ..params.clone()
+        };
+        Blake2xbState {
+            root: params.to_state(),
+            params,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.root.update(input)
+    }
+
+    pub fn finalize_xof(self) -> Blake2xbReader {
+        Blake2xbReader {
+            root_hash: self.root.finalize(),
+            params: Blake2bParams {
+                key: Zeroizing::new(Vec::new()),
+                fanout: 0,
+                max_depth: 0,
+                max_leaf_len: 64,
+                inner_hash_len: 64,
+                ..self.params
+            },
+            block: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+/// Gives the output of BLAKE2XB. Every 64 byte block of the output is the BLAKE2B hash of the root hash with its own node offset.
+#[derive(Debug, Clone)]
+pub struct Blake2xbReader {
+    root_hash: Vec<u8>,
+    params: Blake2bParams,
+    block: Vec<u8>,
+    position: u64,
+}
+
+impl Blake2xbReader {
+    /// ## Panics
+    /// if more than the output length given to `Blake2xbState::new` is read in total
+    pub fn read(&mut self, output: &mut [u8]) {
+        let output_len = u64::from(self.params.xof_len);
+        assert!(
+            self.position + output.len() as u64 <= output_len,
+            "Read past the BLAKE2X output length"
+        );
+        for byte in output.iter_mut() {
+            if self.position.is_multiple_of(64) {
+                let mut block_state = Blake2bParams {
+                    hash_len: (output_len - self.position).min(64) as u8,
+                    node_offset: self.position / 64,
+                    ..self.params.clone()
+                }
+                .to_state();
+                block_state.update(&self.root_hash);
+                self.block = block_state.finalize();
+            }
+            *byte = self.block[(self.position % 64) as usize];
+            self.position += 1;
+        }
+    }
+}
+
+/// The BLAKE2XB extendable output function. Gives `output_len` bytes, which can be up to 2^32 - 1.<br>Key is optional and if there is one, it should be less then 64 bytes.
+///
+/// ## Panics
+/// if `output_len == 0`<br>
+/// if `key.unwrap_or(vec![]).len() > 64`
+pub fn blake2xb(input: &[u8], key: Option<Vec<u8>>, output_len: u32) -> Vec<u8> {
+    let params = Blake2bParams::new().key(&key.unwrap_or_default());
+    let mut state = Blake2xbState::new(output_len, &params);
+    state.update(input);
+    let mut output = vec![0x00; output_len as usize];
+    state.finalize_xof().read(&mut output);
+    output
+}
+
+/// Incremental BLAKE2XS extendable output function. The key, salt and personalization are taken
+/// from the given parameters and BLAKE2X sets the rest of the parameter block.
+#[derive(Debug, Clone)]
+pub struct Blake2xsState {
+    root: Blake2sState,
+    params: Blake2sParams,
+}
+
+impl Blake2xsState {
+    /// ## Panics
+    /// if `output_len == 0`
+    pub fn new(output_len: u16, params: &Blake2sParams) -> Blake2xsState {
+        assert_ne!(output_len, 0);
+        let params = Blake2sParams {
+            hash_len: 32,
+            fanout: 1,
+            max_depth: 1,
+            max_leaf_len: 0,
+            node_offset: 0,
+            node_depth: 0,
+            inner_hash_len: 0,
+            xof_len: output_len,
+            last_node: false,
+            ..params.clone()
+        };
+        Blake2xsState {
+            root: params.to_state(),
+            params,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.root.update(input)
+    }
+
+    pub fn finalize_xof(self) -> Blake2xsReader {
+        Blake2xsReader {
+            root_hash: self.root.finalize(),
+            params: Blake2sParams {
+                key: Zeroizing::new(Vec::new()),
+                fanout: 0,
+                max_depth: 0,
+                max_leaf_len: 32,
+                inner_hash_len: 32,
+                ..self.params
+            },
+            block: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+/// Gives the output of BLAKE2XS. Every 32 byte block of the output is the BLAKE2S hash of the root hash with its own node offset.
+#[derive(Debug, Clone)]
+pub struct Blake2xsReader {
+    root_hash: Vec<u8>,
+    params: Blake2sParams,
+    block: Vec<u8>,
+    position: u64,
+}
+
+impl Blake2xsReader {
+    /// ## Panics
+    /// if more than the output length given to `Blake2xsState::new` is read in total
+    pub fn read(&mut self, output: &mut [u8]) {
+        let output_len = u64::from(self.params.xof_len);
+        assert!(
+            self.position + output.len() as u64 <= output_len,
+            "Read past the BLAKE2X output length"
+        );
+        for byte in output.iter_mut() {
+            if self.position.is_multiple_of(32) {
+                let mut block_state = Blake2sParams {
+                    hash_len: (output_len - self.position).min(32) as u8,
+                    node_offset: self.position / 32,
+                    ..self.params.clone()
+                }
+                .to_state();
+                block_state.update(&self.root_hash);
+                self.block = block_state.finalize();
+            }
+            *byte = self.block[(self.position % 32) as usize];
+            self.position += 1;
+        }
+    }
+}
+
+/// The BLAKE2XS extendable output function. Gives `output_len` bytes, which can be up to 2^16 - 1.<br>Key is optional and if there is one, it should be less then 32 bytes.
+///
+/// ## Panics
+/// if `output_len == 0`<br>
+/// if `key.unwrap_or(vec![]).len() > 32`
+pub fn blake2xs(input: &[u8], key: Option<Vec<u8>>, output_len: u16) -> Vec<u8> {
+    let params = Blake2sParams::new().key(&key.unwrap_or_default());
+    let mut state = Blake2xsState::new(output_len, &params);
+    state.update(input);
+    let mut output = vec![0x00; output_len as usize];
+    state.finalize_xof().read(&mut output);
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -451,4 +1434,322 @@ mod tests {
         .unwrap();
         assert_eq!(blake2s(message4, key4), expected4);
     }
+
+    #[test]
+    fn blake2_rfc7693_test() {
+        // Appendix A and B of RFC 7693
+        let expected2b: [u8; 64] = const_decode_to_array(b"ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap();
+        assert_eq!(blake2b::<64>(b"abc", None), expected2b);
+        let expected2s: [u8; 32] = const_decode_to_array(
+            b"508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+        )
+        .unwrap();
+        assert_eq!(blake2s::<32>(b"abc", None), expected2s);
+    }
+
+    /// Deterministic sequence used by the self tests in appendix E of RFC 7693
+    fn selftest_seq(len: usize, seed: u32) -> Vec<u8> {
+        let mut a: u32 = 0xDEAD4BAD_u32.wrapping_mul(seed);
+        let mut b: u32 = 1;
+        (0..len)
+            .map(|_| {
+                let t = a.wrapping_add(b);
+                a = b;
+                b = t;
+                (t >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blake2b_rfc7693_selftest() {
+        let expected: [u8; 32] = const_decode_to_array(
+            b"c23a7800d98123bd10f506c61e29da5603d763b8bbad2e737f5e765a7bccd475",
+        )
+        .unwrap();
+        let mut grand_hash = Blake2bParams::new().hash_length(32).to_state();
+        for hash_len in [20, 32, 48, 64] {
+            for input_len in [0, 3, 128, 129, 255, 1024] {
+                let input = selftest_seq(input_len, input_len as u32);
+                let mut unkeyed = Blake2bParams::new().hash_length(hash_len).to_state();
+                unkeyed.update(&input);
+                grand_hash.update(&unkeyed.finalize());
+                let key = selftest_seq(hash_len, hash_len as u32);
+                let mut keyed = Blake2bParams::new()
+                    .hash_length(hash_len)
+                    .key(&key)
+                    .to_state();
+                keyed.update(&input);
+                grand_hash.update(&keyed.finalize());
+            }
+        }
+        assert_eq!(grand_hash.finalize(), expected);
+    }
+
+    #[test]
+    fn blake2s_rfc7693_selftest() {
+        let expected: [u8; 32] = const_decode_to_array(
+            b"6a411f08ce25adcdfb02aba641451cec53c598b24f4fc787fbdc88797f4c1dfe",
+        )
+        .unwrap();
+        let mut grand_hash = Blake2sParams::new().hash_length(32).to_state();
+        for hash_len in [16, 20, 28, 32] {
+            for input_len in [0, 3, 64, 65, 255, 1024] {
+                let input = selftest_seq(input_len, input_len as u32);
+                let mut unkeyed = Blake2sParams::new().hash_length(hash_len).to_state();
+                unkeyed.update(&input);
+                grand_hash.update(&unkeyed.finalize());
+                let key = selftest_seq(hash_len, hash_len as u32);
+                let mut keyed = Blake2sParams::new()
+                    .hash_length(hash_len)
+                    .key(&key)
+                    .to_state();
+                keyed.update(&input);
+                grand_hash.update(&keyed.finalize());
+            }
+        }
+        assert_eq!(grand_hash.finalize(), expected);
+    }
+
+    #[test]
+    fn blake2_parameter_block_test() {
+        let mut state2b = Blake2bParams::new()
+            .hash_length(32)
+            .salt(b"saltsaltsaltsalt")
+            .personalization(b"personalization!")
+            .fanout(2)
+            .max_depth(3)
+            .max_leaf_length(4096)
+            .node_offset(5)
+            .node_depth(1)
+            .inner_hash_length(64)
+            .last_node(true)
+            .to_state();
+        state2b.update(b"abc");
+        let expected2b: [u8; 32] = const_decode_to_array(
+            b"5dbeefad3a6127cb0c6b76725c15ce61f4e45f1ba71b93d80c162bf1126bd8e8",
+        )
+        .unwrap();
+        assert_eq!(state2b.finalize(), expected2b);
+
+        let mut keyed2b = Blake2bParams::new()
+            .key(b"key")
+            .salt(b"salt")
+            .personalization(b"me")
+            .to_state();
+        keyed2b.update(b"The quick brown fox jumps over the lazy dog");
+        let expected_keyed2b: [u8; 64] = const_decode_to_array(b"5a73a6cc15a44205379fd8ec53ebfbdaca6a41f066bd23efa22663554a02404df15918870599c7aa60f4fcc4e0dcb8c351b3231b6ecbd7914130c0a4f36ea86c").unwrap();
+        assert_eq!(keyed2b.finalize(), expected_keyed2b);
+
+        let mut state2s = Blake2sParams::new()
+            .hash_length(16)
+            .salt(b"saltsalt")
+            .personalization(b"person!!")
+            .fanout(2)
+            .max_depth(3)
+            .max_leaf_length(4096)
+            .node_offset((1 << 40) + 5)
+            .node_depth(1)
+            .inner_hash_length(32)
+            .last_node(true)
+            .to_state();
+        state2s.update(b"abc");
+        let expected2s: [u8; 16] =
+            const_decode_to_array(b"305837fda7b178c7df513a3f9d570d90").unwrap();
+        assert_eq!(state2s.finalize(), expected2s);
+
+        let mut keyed2s = Blake2sParams::new()
+            .key(b"key")
+            .salt(b"salt")
+            .personalization(b"me")
+            .to_state();
+        keyed2s.update(b"The quick brown fox jumps over the lazy dog");
+        let expected_keyed2s: [u8; 32] = const_decode_to_array(
+            b"fd194aa7c93a22eba505776d34e07f1dbba54c77f0b83a33c97c80635dc03751",
+        )
+        .unwrap();
+        assert_eq!(keyed2s.finalize(), expected_keyed2s);
+    }
+
+    #[test]
+    fn blake2_incremental_test() {
+        let message = selftest_seq(1000, 1000);
+        for len in [0, 1, 63, 64, 65, 127, 128, 129, 256, 1000] {
+            let input = &message[..len];
+            for piece_size in [1, 7, 64, 128, 300] {
+                let mut state2b = Blake2bParams::new().key(b"yaas").to_state();
+                let mut state2s = Blake2sParams::new().key(b"yaas").to_state();
+                for piece in input.chunks(piece_size) {
+                    state2b.update(piece);
+                    state2s.update(piece);
+                }
+                assert_eq!(
+                    state2b.finalize(),
+                    blake2b::<64>(input, Some(b"yaas".to_vec()))
+                );
+                assert_eq!(
+                    state2s.finalize(),
+                    blake2s::<32>(input, Some(b"yaas".to_vec()))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blake2_debug_hides_key_test() {
+        let key = [0xab; 20];
+        let key_bytes = format!("{:?}", &key[..2]);
+        let key_bytes = key_bytes.trim_matches(['[', ']']);
+        let params2b = Blake2bParams::new().key(&key);
+        let params2s = Blake2sParams::new().key(&key);
+        for debug in [
+            format!("{params2b:?}"),
+            format!("{params2s:?}"),
+            format!("{:?}", params2b.to_state()),
+            format!("{:?}", params2s.to_state()),
+            format!("{:?}", Blake2bpState::new(64, &key)),
+            format!("{:?}", Blake2xsState::new(100, &params2s)),
+        ] {
+            assert!(!debug.contains(key_bytes), "{debug}");
+        }
+    }
+
+    #[test]
+    fn blake2_digest_test() {
+        let message = selftest_seq(1000, 1000);
+        assert_eq!(
+            Blake2b::<64>::digest(&message),
+            blake2b::<64>(&message, None)
+        );
+        assert_eq!(
+            Blake2b::<20>::digest(&message),
+            blake2b::<20>(&message, None)
+        );
+        assert_eq!(
+            Blake2s::<32>::digest(&message),
+            blake2s::<32>(&message, None)
+        );
+        assert_eq!(
+            Blake2s::<16>::digest(&message),
+            blake2s::<16>(&message, None)
+        );
+        assert_eq!(Blake2b::<48>::OUTPUT_SIZE, 48);
+        assert_eq!(Blake2s::<32>::BLOCK_SIZE, 64);
+        assert_eq!(
+            BLAKE3::Blake3::digest(&message),
+            BLAKE3::blake3::<32>(&message)
+        );
+    }
+
+    #[test]
+    fn blake2bp_test() {
+        let message = selftest_seq(1000, 1000);
+        let expected_empty: [u8; 64] = const_decode_to_array(b"b5ef811a8038f70b628fa8b294daae7492b1ebe343a80eaabbf1f6ae664dd67b9d90b0120791eab81dc96985f28849f6a305186a85501b405114bfa678df9380").unwrap();
+        assert_eq!(blake2bp::<64>(b"", None), expected_empty);
+        let expected_message: [u8; 64] = const_decode_to_array(b"cabfb0a63f8823c2cb48096ff6d8c7b90c5391ba6be085c09ecbefc4525fd59db1d9314515581a8b3a87403e1fa6643f058d7df1c6fbe3f35bf39c242bab12e1").unwrap();
+        assert_eq!(blake2bp::<64>(&message, None), expected_message);
+        // First vector of blake2bp-kat.txt in the reference implementation
+        let key: Vec<u8> = (0..64).collect();
+        let expected_keyed: [u8; 64] = const_decode_to_array(b"9d9461073e4eb640a255357b839f394b838c6ff57c9b686a3f76107c1066728f3c9956bd785cbc3bf79dc2ab578c5a0c063b9d9c405848de1dbe821cd05c940a").unwrap();
+        assert_eq!(blake2bp::<64>(b"", Some(key)), expected_keyed);
+    }
+
+    #[test]
+    fn blake2sp_test() {
+        let message = selftest_seq(1000, 1000);
+        let expected_empty: [u8; 32] = const_decode_to_array(
+            b"dd0e891776933f43c7d032b08a917e25741f8aa9a12c12e1cac8801500f2ca4f",
+        )
+        .unwrap();
+        assert_eq!(blake2sp::<32>(b"", None), expected_empty);
+        let expected_message: [u8; 32] = const_decode_to_array(
+            b"6ef643a007f726e89b152a0c58fbd656fba5cd59996274cd79aad996af2067df",
+        )
+        .unwrap();
+        assert_eq!(blake2sp::<32>(&message, None), expected_message);
+        // First vector of blake2sp-kat.txt in the reference implementation
+        let key: Vec<u8> = (0..32).collect();
+        let expected_keyed: [u8; 32] = const_decode_to_array(
+            b"715cb13895aeb678f6124160bff21465b30f4f6874193fc851b4621043f09cc6",
+        )
+        .unwrap();
+        assert_eq!(blake2sp::<32>(b"", Some(key)), expected_keyed);
+    }
+
+    #[test]
+    fn blake2_parallel_incremental_test() {
+        let key = b"yaas".to_vec();
+        // Long enough to be hashed with threads in the one shot functions
+        let message = selftest_seq(PARALLEL_THRESHOLD + 1000, 1);
+        for len in [0, 1, 128, 512, 513, 1000, PARALLEL_THRESHOLD + 1000] {
+            let input = &message[..len];
+            for piece_size in [7, 128, 700] {
+                let mut state2bp = Blake2bpState::new(48, &key);
+                let mut state2sp = Blake2spState::new(20, &key);
+                for piece in input.chunks(piece_size) {
+                    state2bp.update(piece);
+                    state2sp.update(piece);
+                }
+                assert_eq!(
+                    state2bp.finalize(),
+                    blake2bp::<48>(input, Some(key.clone()))
+                );
+                assert_eq!(
+                    state2sp.finalize(),
+                    blake2sp::<20>(input, Some(key.clone()))
+                );
+            }
+        }
+    }
+    #[test]
+    fn blake2x_test() {
+        // First and 256th vectors of blake2xb-kat.txt and blake2xs-kat.txt in the reference implementation
+        let input: Vec<u8> = (0..=255).collect();
+        let key2b: Vec<u8> = (0..64).collect();
+        assert_eq!(blake2xb(&input, Some(key2b.clone()), 1), [0x64]);
+        let expected2b: [u8; 256] = const_decode_to_array(b"1e9b2c454e9de3a2d723d850331037dbf54133dbe27488ff757dd255833a27d8eb8a128ad12d0978b6884e25737086a704fb289aaaccf930d5b582ab4df1f55f0c429b6875edec3fe45464fa74164be056a55e243c4222c586bec5b18f39036aa903d98180f24f83d09a454dfa1e03a60e6a3ba4613e99c35f874d790174ee48a557f4f021ade4d1b278d7997ef094569b37b3db0505951e9ee8400adaea275c6db51b325ee730c69df97745b556ae41cd98741e28aa3a49544541eeb3da1b1e8fa4e8e9100d66dd0c7f5e2c271b1ecc077de79c462b9fe4c273543ecd82a5bea63c5acc01eca5fb780c7d7c8c9fe208ae8bd50cad1769693d92c6c8649d20d8").unwrap();
+        assert_eq!(blake2xb(&input, Some(key2b), 256), expected2b);
+
+        let key2s: Vec<u8> = (0..32).collect();
+        assert_eq!(blake2xs(&input, Some(key2s.clone()), 1), [0x0e]);
+        let expected2s: [u8; 256] = const_decode_to_array(b"5784e614d538f7f26c803191deb464a884817002988c36448dcbecfad1997fe51ab0b3853c51ed49ce9f4e477522fb3f32cc50515b753c18fb89a8d965afcf1ed5e099b22c4225732baeb986f5c5bc88e4582d27915e2a19126d3d4555fab4f6516a6a156dbfeed9e982fc589e33ce2b9e1ba2b416e11852ddeab93025974267ac82c84f071c3d07f215f47e3565fd1d962c76e0d635892ea71488273765887d31f250a26c4ddc377ed89b17326e259f6cc1de0e63158e83aebb7f5a7c08c63c767876c8203639958a407acca096d1f606c04b4f4b3fd771781a5901b1c3cee7c04c3b6870226eee309b74f51edbf70a3817cc8da87875301e04d0416a65dc5d").unwrap();
+        assert_eq!(blake2xs(&input, Some(key2s), 256), expected2s);
+    }
+
+    #[test]
+    fn blake2x_reader_test() {
+        let expected2b: [u8; 100] = const_decode_to_array(b"c5fa93d8aaeecc5fb6ae7bfb85dbe88b80045f3fe48ca804ce664280d8f36f06267a5d69e78e5e6f91255d5a93a025ba0ace40bc91a6d4946620d3c39ccedbc806923d94e2fa0d99e7b229a535b63a52808d99794b63759e62e0e996a84c051e401a0003").unwrap();
+        let mut state2b = Blake2xbState::new(
+            100,
+            &Blake2bParams::new().salt(b"salt").personalization(b"me"),
+        );
+        state2b.update(b"a");
+        state2b.update(b"bc");
+        let mut reader2b = state2b.finalize_xof();
+        let mut output2b = [0x00; 100];
+        let (first, second) = output2b.split_at_mut(63);
+        reader2b.read(first);
+        reader2b.read(second);
+        assert_eq!(output2b, expected2b);
+
+        let expected2s: [u8; 100] = const_decode_to_array(b"f6292f8587cff912a46e505f3016178003ba820d7b1b8109d23f4798159c5256b4bf2885d4a2c72cf92934ffc79861e35a2d47dea80a1b762f727a999b0cc923277d516576b4d89cc191a0e29948dfa38edfbc1732fd75f0f9515f2d99aaa8c78def49c0").unwrap();
+        let mut state2s = Blake2xsState::new(
+            100,
+            &Blake2sParams::new().salt(b"salt").personalization(b"me"),
+        );
+        state2s.update(b"abc");
+        let mut reader2s = state2s.finalize_xof();
+        let mut output2s = [0x00; 100];
+        for byte in output2s.chunks_mut(1) {
+            reader2s.read(byte);
+        }
+        assert_eq!(output2s, expected2s);
+    }
+
+    #[test]
+    #[should_panic]
+    fn blake2x_read_past_end_test() {
+        let mut reader = Blake2xbState::new(10, &Blake2bParams::new()).finalize_xof();
+        reader.read(&mut [0x00; 11]);
+    }
 }
@@ -0,0 +1,486 @@
+use super::{mix_2s, INITIALIZATION_VECTOR_2S};
+use crate::Integrity::Digest;
+
+const MESSAGE_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+const CHUNK_LEN: usize = 1024;
+const BLOCK_LEN: usize = 64;
+
+// Domain separation flags
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+fn compress(
+    chaining_value: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut work_vector: [u32; 16] = [
+        chaining_value[0],
+        chaining_value[1],
+        chaining_value[2],
+        chaining_value[3],
+        chaining_value[4],
+        chaining_value[5],
+        chaining_value[6],
+        chaining_value[7],
+        INITIALIZATION_VECTOR_2S[0],
+        INITIALIZATION_VECTOR_2S[1],
+        INITIALIZATION_VECTOR_2S[2],
+        INITIALIZATION_VECTOR_2S[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut message = *block_words;
+
+    // Same blender as BLAKE2S, but with 7 rounds and the message permuted in between instead of SIGMA
+    for _ in 0..7 {
+        mix_2s(&mut work_vector, 0, 4, 8, 12, message[0], message[1]);
+        mix_2s(&mut work_vector, 1, 5, 9, 13, message[2], message[3]);
+        mix_2s(&mut work_vector, 2, 6, 10, 14, message[4], message[5]);
+        mix_2s(&mut work_vector, 3, 7, 11, 15, message[6], message[7]);
+        mix_2s(&mut work_vector, 0, 5, 10, 15, message[8], message[9]);
+        mix_2s(&mut work_vector, 1, 6, 11, 12, message[10], message[11]);
+        mix_2s(&mut work_vector, 2, 7, 8, 13, message[12], message[13]);
+        mix_2s(&mut work_vector, 3, 4, 9, 14, message[14], message[15]);
+        message = MESSAGE_PERMUTATION.map(|i| message[i]);
+    }
+
+    for i in 0..8 {
+        work_vector[i] ^= work_vector[i + 8];
+        work_vector[i + 8] ^= chaining_value[i];
+    }
+    work_vector
+}
+
+fn words_from_le_bytes<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (word, bytes) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    words
+}
+
+/// Everything needed for the last compression of a node, so it can either be a chaining value
+/// or be used as the root to make any amount of output
+#[derive(Debug, Clone)]
+struct Output {
+    input_chaining_value: [u32; 8],
+    block_words: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            self.counter,
+            self.block_len,
+            self.flags,
+        )[..8]
+            .try_into()
+            .unwrap()
+    }
+
+    fn root_output_block(&self, output_block_counter: u64) -> [u8; BLOCK_LEN] {
+        let words = compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            output_block_counter,
+            self.block_len,
+            self.flags | ROOT,
+        );
+        let mut block = [0x00; BLOCK_LEN];
+        for (bytes, word) in block.chunks_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        block
+    }
+}
+
+fn parent_output(
+    left_child: [u32; 8],
+    right_child: [u32; 8],
+    key_words: [u32; 8],
+    flags: u32,
+) -> Output {
+    let mut block_words = [0u32; 16];
+    block_words[..8].copy_from_slice(&left_child);
+    block_words[8..].copy_from_slice(&right_child);
+    Output {
+        input_chaining_value: key_words,
+        block_words,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT | flags,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChunkState {
+    chaining_value: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key_words: [u32; 8], chunk_counter: u64, flags: u32) -> ChunkState {
+        ChunkState {
+            chaining_value: key_words,
+            chunk_counter,
+            block: [0x00; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        let mut input = input;
+        while !input.is_empty() {
+            // The last block has to be kept for the output, so only compress when there is more input
+            if self.block_len == BLOCK_LEN {
+                self.chaining_value = compress(
+                    &self.chaining_value,
+                    &words_from_le_bytes(&self.block),
+                    self.chunk_counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                )[..8]
+                    .try_into()
+                    .unwrap();
+                self.blocks_compressed += 1;
+                self.block = [0x00; BLOCK_LEN];
+                self.block_len = 0;
+            }
+            let amount = (BLOCK_LEN - self.block_len).min(input.len());
+            self.block[self.block_len..self.block_len + amount].copy_from_slice(&input[..amount]);
+            self.block_len += amount;
+            input = &input[amount..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            input_chaining_value: self.chaining_value,
+            block_words: words_from_le_bytes(&self.block),
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+/// Incremental BLAKE3 hasher, for the plain hash, keyed hash and key derivation modes
+#[derive(Debug, Clone)]
+pub struct Blake3 {
+    chunk_state: ChunkState,
+    key_words: [u32; 8],
+    // Chaining values of complete subtrees that still need a right sibling
+    chaining_value_stack: Vec<[u32; 8]>,
+    flags: u32,
+}
+
+impl Blake3 {
+    fn new_internal(key_words: [u32; 8], flags: u32) -> Blake3 {
+        Blake3 {
+            chunk_state: ChunkState::new(key_words, 0, flags),
+            key_words,
+            chaining_value_stack: Vec::new(),
+            flags,
+        }
+    }
+
+    pub fn new() -> Blake3 {
+        Self::new_internal(INITIALIZATION_VECTOR_2S, 0)
+    }
+
+    pub fn new_keyed(key: &[u8; 32]) -> Blake3 {
+        Self::new_internal(words_from_le_bytes(key), KEYED_HASH)
+    }
+
+    /// The context should be hardcoded, globally unique and application specific
+    pub fn new_derive_key(context: &str) -> Blake3 {
+        let mut context_hasher = Self::new_internal(INITIALIZATION_VECTOR_2S, DERIVE_KEY_CONTEXT);
+        context_hasher.update(context.as_bytes());
+        let context_key: [u8; 32] = context_hasher.finalize();
+        Self::new_internal(words_from_le_bytes(&context_key), DERIVE_KEY_MATERIAL)
+    }
+
+    // Merges completed subtrees, total_chunks has a trailing zero bit for every merge that can be made
+    fn add_chunk_chaining_value(&mut self, mut chaining_value: [u32; 8], mut total_chunks: u64) {
+        while total_chunks & 1 == 0 {
+            let left_child = self
+                .chaining_value_stack
+                .pop()
+                .expect("a left sibling for every trailing zero");
+            chaining_value = parent_output(left_child, chaining_value, self.key_words, self.flags)
+                .chaining_value();
+            total_chunks >>= 1;
+        }
+        self.chaining_value_stack.push(chaining_value);
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        let mut input = input;
+        while !input.is_empty() {
+            // Only finish a chunk when more input comes, the last one is needed for the root
+            if self.chunk_state.len() == CHUNK_LEN {
+                let chunk_chaining_value = self.chunk_state.output().chaining_value();
+                let total_chunks = self.chunk_state.chunk_counter + 1;
+                self.add_chunk_chaining_value(chunk_chaining_value, total_chunks);
+                self.chunk_state = ChunkState::new(self.key_words, total_chunks, self.flags);
+            }
+            let amount = (CHUNK_LEN - self.chunk_state.len()).min(input.len());
+            self.chunk_state.update(&input[..amount]);
+            input = &input[amount..];
+        }
+    }
+
+    fn root_output(&self) -> Output {
+        let mut output = self.chunk_state.output();
+        for left_child in self.chaining_value_stack.iter().rev() {
+            output = parent_output(
+                *left_child,
+                output.chaining_value(),
+                self.key_words,
+                self.flags,
+            );
+        }
+        output
+    }
+
+    pub fn finalize<const OUTPUT_LEN: usize>(self) -> [u8; OUTPUT_LEN] {
+        let mut output = [0x00; OUTPUT_LEN];
+        self.finalize_xof().read(&mut output);
+        output
+    }
+
+    pub fn finalize_xof(self) -> Blake3Reader {
+        Blake3Reader {
+            output: self.root_output(),
+            position: 0,
+        }
+    }
+}
+
+impl Default for Blake3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Digest for Blake3 {
+    const OUTPUT_SIZE: usize = 32;
+    const BLOCK_SIZE: usize = BLOCK_LEN;
+    type Output = [u8; 32];
+
+    fn new() -> Self {
+        Blake3::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Blake3::update(self, input)
+    }
+
+    fn finalize(self) -> Self::Output {
+        Blake3::finalize::<32>(self)
+    }
+}
+
+/// Gives the output of BLAKE3, which can be read in parts and goes on for 2^64 bytes
+#[derive(Debug, Clone)]
+pub struct Blake3Reader {
+    output: Output,
+    position: u64,
+}
+
+impl Blake3Reader {
+    pub fn read(&mut self, output: &mut [u8]) {
+        let mut output = output;
+        while !output.is_empty() {
+            let block = self
+                .output
+                .root_output_block(self.position / BLOCK_LEN as u64);
+            let offset = (self.position % BLOCK_LEN as u64) as usize;
+            let amount = (BLOCK_LEN - offset).min(output.len());
+            output[..amount].copy_from_slice(&block[offset..offset + amount]);
+            self.position += amount as u64;
+            output = &mut output[amount..];
+        }
+    }
+}
+
+/// The BLAKE3 hash function. OUTPUT_LEN is the digest length in bytes, 32 is the default length.
+pub fn blake3<const OUTPUT_LEN: usize>(input: &[u8]) -> [u8; OUTPUT_LEN] {
+    let mut hasher = Blake3::new();
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// The keyed BLAKE3 hash function, which can be used as a MAC.
+pub fn blake3_keyed<const OUTPUT_LEN: usize>(key: &[u8; 32], input: &[u8]) -> [u8; OUTPUT_LEN] {
+    let mut hasher = Blake3::new_keyed(key);
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// BLAKE3 in key derivation mode. Derives a key from `key_material` for the use given by `context`.
+pub fn blake3_derive_key<const OUTPUT_LEN: usize>(
+    context: &str,
+    key_material: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut hasher = Blake3::new_derive_key(context);
+    hasher.update(key_material);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use const_hex::const_decode_to_array;
+
+    const TEST_KEY: &[u8; 32] = b"whats the Elvish word for friend";
+    const TEST_CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    fn test_input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn blake3_test() {
+        // Cases from test_vectors.json of the reference implementation
+        let cases = [
+            (
+                0usize,
+                b"af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+                b"92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26",
+                b"2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d",
+            ),
+            (
+                1,
+                b"2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213",
+                b"6d7878dfff2f485635d39013278ae14f1454b8c0a3a2d34bc1ab38228a80c95b",
+                b"b3e2e340a117a499c6cf2398a19ee0d29cca2bb7404c73063382693bf66cb06c",
+            ),
+            (
+                1023,
+                b"10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11",
+                b"c951ecdf03288d0fcc96ee3413563d8a6d3589547f2c2fb36d9786470f1b9d6e",
+                b"74a16c1c3d44368a86e1ca6df64be6a2f64cce8f09220787450722d85725dea5",
+            ),
+            (
+                1024,
+                b"42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7",
+                b"75c46f6f3d9eb4f55ecaaee480db732e6c2105546f1e675003687c31719c7ba4",
+                b"7356cd7720d5b66b6d0697eb3177d9f8d73a4a5c5e968896eb6a689684302706",
+            ),
+            (
+                1025,
+                b"d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+                b"357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea69",
+                b"effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb",
+            ),
+            (
+                2049,
+                b"5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b6879522563030",
+                b"9f29700902f7c86e514ddc4df1e3049f258b2472b6dd5267f61bf13983b78dd5",
+                b"2ea477c5515cc3dd606512ee72bb3e0e758cfae7232826f35fb98ca1bcbdf273",
+            ),
+            (
+                8193,
+                b"bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b",
+                b"954a2a75420c8d6547e3ba5b98d963e6fa6491addc8c023189cc519821b4a1f5",
+                b"af1e0346e389b17c23200270a64aa4e1ead98c61695d917de7d5b00491c9b0f1",
+            ),
+            (
+                102400,
+                b"bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085",
+                b"1c35d1a5811083fd7119f5d5d1ba027b4d01c0c6c49fb6ff2cf75393ea5db4a7",
+                b"4652cff7a3f385a6103b5c260fc1593e13c778dbe608efb092fe7ee69df6e9c6",
+            ),
+        ];
+        for (len, hash, keyed_hash, derived_key) in cases {
+            let input = test_input(len);
+            let expected: [u8; 32] = const_decode_to_array(hash).unwrap();
+            assert_eq!(blake3::<32>(&input), expected, "hash of {len} bytes");
+            let expected: [u8; 32] = const_decode_to_array(keyed_hash).unwrap();
+            assert_eq!(
+                blake3_keyed::<32>(TEST_KEY, &input),
+                expected,
+                "keyed hash of {len} bytes"
+            );
+            let expected: [u8; 32] = const_decode_to_array(derived_key).unwrap();
+            assert_eq!(
+                blake3_derive_key::<32>(TEST_CONTEXT, &input),
+                expected,
+                "derived key of {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn blake3_xof_test() {
+        let input = test_input(1025);
+        let expected1: [u8; 131] = const_decode_to_array(b"d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444f4c4a22b4b399155358a994e52bf255de60035742ec71bd08ac275a1b51cc6bfe332b0ef84b409108cda080e6269ed4b3e2c3f7d722aa4cdc98d16deb554e5627be8f955c98e1d5f9565a9194cad0c4285f93700062d9595adb992ae68ff12800ab67a").unwrap();
+        assert_eq!(blake3::<131>(&input), expected1);
+
+        let expected2: [u8; 131] = const_decode_to_array(b"357dc55de0c7e382c900fd6e320acc04146be01db6a8ce7210b7189bd664ea69362396b77fdc0d2634a552970843722066c3c15902ae5097e00ff53f1e116f1cd5352720113a837ab2452cafbde4d54085d9cf5d21ca613071551b25d52e69d6c81123872b6f19cd3bc1333edf0c52b94de23ba772cf82636cff4542540a7738d5b930").unwrap();
+        let mut hasher = Blake3::new_keyed(TEST_KEY);
+        hasher.update(&input);
+        let mut reader = hasher.finalize_xof();
+        let mut output = [0x00; 131];
+        let (first, rest) = output.split_at_mut(10);
+        let (second, third) = rest.split_at_mut(60);
+        reader.read(first);
+        reader.read(second);
+        reader.read(third);
+        assert_eq!(output, expected2);
+
+        let expected3: [u8; 131] = const_decode_to_array(b"effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb5d31013a167509e9066273ab6e2123bc835b408b067d88f96addb550d96b6852dad38e320b9d940f86db74d398c770f462118b35d2724efa13da97194491d96dd37c3c09cbef665953f2ee85ec83d88b88d11547a6f911c8217cca46defa2751e7f3ad").unwrap();
+        assert_eq!(blake3_derive_key::<131>(TEST_CONTEXT, &input), expected3);
+    }
+
+    #[test]
+    fn blake3_incremental_test() {
+        let input = test_input(8193);
+        for split in [1, 63, 64, 65, 1023, 1024, 1025, 4096] {
+            let mut hasher = Blake3::new();
+            for part in input.chunks(split) {
+                hasher.update(part);
+            }
+            assert_eq!(
+                hasher.finalize::<32>(),
+                blake3::<32>(&input),
+                "split {split}"
+            );
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use super::Digest;
+use super::SHA::SHA2::{Sha256, Sha512};
+use crate::Authenticity::AuthenticationError;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Incremental HMAC (RFC 2104) over any hash that implements `Digest`, e.g. `Hmac::<Sha256>::new(key)`
+#[derive(Clone)]
+pub struct Hmac<D: Digest> {
+    inner: D,
+    outer: D,
+}
+
+impl<D: Digest> Hmac<D> {
+    /// Keys longer than the block size of the hash are hashed first
+    ///
+    /// ## Panics
+    /// if the digest of `D` is longer than its block size
+    pub fn new(key: &[u8]) -> Hmac<D> {
+        assert!(D::OUTPUT_SIZE <= D::BLOCK_SIZE);
+        // Everything derived from the key is wiped when it goes out of scope
+        let mut block_key = Zeroizing::new(vec![0x00; D::BLOCK_SIZE]);
+        if key.len() > D::BLOCK_SIZE {
+            let mut hashed_key = D::digest(key);
+            block_key[..D::OUTPUT_SIZE].copy_from_slice(hashed_key.as_ref());
+            hashed_key.as_mut().zeroize();
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let padded_key =
+            |pad: u8| Zeroizing::new(block_key.iter().map(|b| b ^ pad).collect::<Vec<u8>>());
+        let mut inner = D::new();
+        inner.update(&padded_key(0x36));
+        let mut outer = D::new();
+        outer.update(&padded_key(0x5c));
+        Hmac { inner, outer }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input)
+    }
+
+    pub fn finalize(self) -> D::Output {
+        let inner_hash = self.inner.finalize();
+        let mut outer = self.outer;
+        outer.update(inner_hash.as_ref());
+        outer.finalize()
+    }
+
+    /// Checks the tag against the message in constant time
+    pub fn verify(self, tag: &[u8]) -> Result<(), AuthenticationError> {
+        match constant_time_eq(self.finalize().as_ref(), tag) {
+            true => Ok(()),
+            false => Err(AuthenticationError::TagNotMatchMessage),
+        }
+    }
+}
+
+/// Compares two byte slices without stopping at the first difference, so the time taken
+/// does not depend on where they differ. Only the lengths are compared in variable time.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(difference) == 0
+}
+
+/// HMAC of the message with the hash function `D`
+pub fn hmac<D: Digest>(key: &[u8], message: &[u8]) -> D::Output {
+    let mut mac = Hmac::<D>::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+/// Checks in constant time whether `tag` is the HMAC of the message with the hash function `D`
+pub fn hmac_verify<D: Digest>(
+    key: &[u8],
+    message: &[u8],
+    tag: &[u8],
+) -> Result<(), AuthenticationError> {
+    let mut mac = Hmac::<D>::new(key);
+    mac.update(message);
+    mac.verify(tag)
+}
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    hmac::<Sha256>(key, message)
+}
+
+pub fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+    hmac::<Sha512>(key, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Integrity::SHA::SHA1::Sha1;
+    use crate::Integrity::SHA::SHA2::{Sha224, Sha384};
+    use crate::Integrity::SHA::SHA3::{Sha3_256, Sha3_512};
+    use const_hex::encode;
+
+    // Keys and messages of the test cases in RFC 4231, without the truncated test case 5
+    fn rfc4231_cases() -> [(Vec<u8>, Vec<u8>); 6] {
+        [
+            ([0x0b].repeat(20), b"Hi There".to_vec()),
+            (b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec()),
+            ([0xaa].repeat(20), [0xdd].repeat(50)),
+            ((0x01..=0x19).collect(), [0xcd].repeat(50)),
+            (
+                [0xaa].repeat(131),
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+            ),
+            (
+                [0xaa].repeat(131),
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.".to_vec(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn hmac_sha224_test() {
+        let expected = [
+            "896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22",
+            "a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44",
+            "7fb3cb3588c6c1f6ffa9694d7d6ad2649365b0c1f65d69d1ec8333ea",
+            "6c11506874013cac6a2abc1bb382627cec6a90d86efc012de7afec5a",
+            "95e9a0db962095adaebe9b2d6f0dbce2d499f112f2d2b7273fa6870e",
+            "3a854166ac5d9f023f54d517d0b39dbd946770db9c2b95c9f6f565d1",
+        ];
+        for ((key, message), expected) in rfc4231_cases().iter().zip(expected) {
+            assert_eq!(encode(hmac::<Sha224>(key, message)), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_sha256_test() {
+        let expected = [
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+        ];
+        for ((key, message), expected) in rfc4231_cases().iter().zip(expected) {
+            assert_eq!(encode(hmac_sha256(key, message)), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_sha384_test() {
+        let expected = [
+            "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6",
+            "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e8e2240ca5e69e2c78b3239ecfab21649",
+            "88062608d3e6ad8a0aa2ace014c8a86f0aa635d947ac9febe83ef4e55966144b2a5ab39dc13814b94e3ab6e101a34f27",
+            "3e8a69b7783c25851933ab6290af6ca77a9981480850009cc5577c6e1f573b4e6801dd23c4a7d679ccf8a386c674cffb",
+            "4ece084485813e9088d2c63a041bc5b44f9ef1012a2b588f3cd11f05033ac4c60c2ef6ab4030fe8296248df163f44952",
+            "6617178e941f020d351e2f254e8fd32c602420feb0b8fb9adccebb82461e99c5a678cc31e799176d3860e6110c46523e",
+        ];
+        for ((key, message), expected) in rfc4231_cases().iter().zip(expected) {
+            assert_eq!(encode(hmac::<Sha384>(key, message)), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_sha512_test() {
+        let expected = [
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb",
+            "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd",
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+            "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58",
+        ];
+        for ((key, message), expected) in rfc4231_cases().iter().zip(expected) {
+            assert_eq!(encode(hmac_sha512(key, message)), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_sha1_test() {
+        // RFC 2202
+        assert_eq!(
+            encode(hmac::<Sha1>(&[0x0b; 20], b"Hi There")),
+            "b617318655057264e28bc0b6fb378c8ef146be00"
+        );
+        assert_eq!(
+            encode(hmac::<Sha1>(b"Jefe", b"what do ya want for nothing?")),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            encode(hmac::<Sha1>(
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+    }
+
+    #[test]
+    fn hmac_sha3_test() {
+        // NIST HMAC-SHA3 examples
+        let key: Vec<u8> = (0x00..0x20).collect();
+        assert_eq!(
+            encode(hmac::<Sha3_256>(
+                &key,
+                b"Sample message for keylen<blocklen"
+            )),
+            "4fe8e202c4f058e8dddc23d8c34e467343e23555e24fc2f025d598f558f67205"
+        );
+        let key: Vec<u8> = (0x00..0xa8).collect();
+        assert_eq!(
+            encode(hmac::<Sha3_256>(
+                &key,
+                b"Sample message for keylen=blocklen"
+            )),
+            "3710a538d012cbc5509939a6cb7048554880d8ee2cd3d67a9ed2f61dfe2cd295"
+        );
+        let key: Vec<u8> = (0x00..0x40).collect();
+        assert_eq!(
+            encode(hmac::<Sha3_512>(&key, b"Sample message for keylen<blocklen")),
+            "4efd629d6c71bf86162658f29943b1c308ce27cdfa6db0d9c3ce81763f9cbce5f7ebe9868031db1a8f8eb7b6b95e5c5e3f657a8996c86a2f6527e307f0213196"
+        );
+    }
+
+    #[test]
+    fn hmac_incremental_verify_test() {
+        let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+        let mut mac = Hmac::<Sha256>::new(b"key");
+        for piece in message.chunks(17) {
+            mac.update(piece);
+        }
+        let tag = mac.clone().finalize();
+        assert_eq!(tag, hmac_sha256(b"key", &message));
+        assert_eq!(mac.verify(&tag), Ok(()));
+
+        let mut bad_tag = tag;
+        bad_tag[31] ^= 0x01;
+        assert_eq!(
+            hmac_verify::<Sha256>(b"key", &message, &bad_tag),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+        assert_eq!(
+            hmac_verify::<Sha256>(b"key", &message, &tag[..16]),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+    }
+}
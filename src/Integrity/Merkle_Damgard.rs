@@ -1,4 +1,3 @@
-
 pub(crate) fn merkle_damgard<STATE, C, P, F, const N: usize, const BLOCK_SIZE_BYTES: usize>(
     pad: P,
     compression: C,
@@ -8,13 +7,124 @@ pub(crate) fn merkle_damgard<STATE, C, P, F, const N: usize, const BLOCK_SIZE_BY
 ) -> [u8; N]
 where
     C: Fn(&mut STATE, [u8; BLOCK_SIZE_BYTES]),
-    P: Fn(&[u8]) -> Box<dyn Iterator<Item = [u8; BLOCK_SIZE_BYTES]> + '_>,
+    P: Fn(&[u8], u128) -> ([[u8; BLOCK_SIZE_BYTES]; 2], usize),
     F: Fn(STATE) -> [u8; N],
 {
-    let padded = pad(input);
     let mut state = IV;
-    for block in padded {
-        compression(&mut state, block);
+    let mut chunks = input.chunks_exact(BLOCK_SIZE_BYTES);
+    for chunk in chunks.by_ref() {
+        compression(
+            &mut state,
+            chunk.try_into().expect("chunk size in BLOCK_SIZE_BYTES"),
+        );
+    }
+    let (padding, padding_blocks) = pad(chunks.remainder(), input.len() as u128);
+    for block in &padding[..padding_blocks] {
+        compression(&mut state, *block);
     }
     finalize(state)
 }
+
+/// Incremental version of `merkle_damgard`. Input is buffered until a whole block is available
+/// and the padding is only applied when finalizing, so the message never has to be in memory at once.
+#[derive(Clone)]
+pub(crate) struct MerkleDamgard<STATE, const BLOCK_SIZE_BYTES: usize> {
+    state: STATE,
+    compression: fn(&mut STATE, [u8; BLOCK_SIZE_BYTES]),
+    buffer: [u8; BLOCK_SIZE_BYTES],
+    buffer_len: usize,
+    message_len: u128,
+}
+
+impl<STATE, const BLOCK_SIZE_BYTES: usize> MerkleDamgard<STATE, BLOCK_SIZE_BYTES> {
+    pub(crate) fn new(IV: STATE, compression: fn(&mut STATE, [u8; BLOCK_SIZE_BYTES])) -> Self {
+        MerkleDamgard {
+            state: IV,
+            compression,
+            buffer: [0x00; BLOCK_SIZE_BYTES],
+            buffer_len: 0,
+            message_len: 0,
+        }
+    }
+
+    /// Continues from a chaining state reached after `message_len` bytes, which has to be a whole number of blocks
+    /// whose length in bits fits in the `BLOCK_SIZE_BYTES` bits the padding has for it
+    pub(crate) fn from_midstate(
+        state: STATE,
+        message_len: u128,
+        compression: fn(&mut STATE, [u8; BLOCK_SIZE_BYTES]),
+    ) -> Self {
+        assert!(
+            message_len.is_multiple_of(BLOCK_SIZE_BYTES as u128),
+            "A midstate has to be after a whole number of blocks"
+        );
+        // message_len * 8 < 2^BLOCK_SIZE_BYTES
+        assert!(
+            message_len.leading_zeros() as usize >= 128 - BLOCK_SIZE_BYTES + 3,
+            "A midstate has to be after fewer bytes than the padding can encode"
+        );
+        MerkleDamgard {
+            state,
+            compression,
+            buffer: [0x00; BLOCK_SIZE_BYTES],
+            buffer_len: 0,
+            message_len,
+        }
+    }
+
+    /// The chaining state and the number of bytes hashed, if no partial block is buffered
+    pub(crate) fn midstate(&self) -> Option<(STATE, u128)>
+    where
+        STATE: Copy,
+    {
+        match self.buffer_len {
+            0 => Some((self.state, self.message_len)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn update(&mut self, input: &[u8]) {
+        self.message_len += input.len() as u128;
+        let mut input = input;
+
+        // Fill up a partially filled buffer first
+        if self.buffer_len > 0 {
+            let needed = (BLOCK_SIZE_BYTES - self.buffer_len).min(input.len());
+            self.buffer[self.buffer_len..self.buffer_len + needed].copy_from_slice(&input[..needed]);
+            self.buffer_len += needed;
+            input = &input[needed..];
+            if self.buffer_len < BLOCK_SIZE_BYTES {
+                return;
+            }
+            (self.compression)(&mut self.state, self.buffer);
+            self.buffer_len = 0;
+        }
+
+        // Compress whole blocks straight from the input
+        let mut chunks = input.chunks_exact(BLOCK_SIZE_BYTES);
+        for chunk in chunks.by_ref() {
+            (self.compression)(
+                &mut self.state,
+                chunk.try_into().expect("chunk size in BLOCK_SIZE_BYTES"),
+            );
+        }
+
+        // Buffer the rest
+        let remainder = chunks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffer_len = remainder.len();
+    }
+
+    /// Pads the buffered bytes with `pad`, which gets the unprocessed bytes and the length of the whole message in bytes
+    /// and gives the padding blocks and how many of them are used.
+    pub(crate) fn finalize<P>(mut self, pad: P) -> STATE
+    where
+        P: Fn(&[u8], u128) -> ([[u8; BLOCK_SIZE_BYTES]; 2], usize),
+    {
+        let (padding, padding_blocks) = pad(&self.buffer[..self.buffer_len], self.message_len);
+        for block in &padding[..padding_blocks] {
+            (self.compression)(&mut self.state, *block);
+        }
+        self.state
+    }
+}
@@ -1,66 +1,140 @@
+pub mod KangarooTwelve;
 pub mod SHA1;
 pub mod SHA2;
 pub mod SHA3;
+pub mod SP800_185;
+#[cfg(test)]
+mod cavp;
 #[cfg(test)]
 mod tests;
 
-use std::iter::once;
+/// The chaining state of a SHA1 or SHA2 hasher after a whole number of blocks and the number of
+/// bytes hashed to get there. A hasher resumed from it gives the same digest as one that hashed those bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Midstate<STATE> {
+    pub state: STATE,
+    pub byte_count: u128,
+}
 
-fn sha_padding<const BLOCK_SIZE_BYTES: usize>(
-    input: &[u8],
-) -> Box<dyn Iterator<Item = [u8; BLOCK_SIZE_BYTES]> + '_> {
-    let lenght = input.len();
-    let u128_length: [u8; 16] = u128::try_from(lenght * 8).unwrap().to_be_bytes();
-    let u64_length: [u8; 8] = u64::try_from(lenght * 8).unwrap().to_be_bytes();
-    let length_bytes = match BLOCK_SIZE_BYTES {
-        64 => u64_length.as_slice(),
-        128 => u128_length.as_slice(),
-        _ => unreachable!(),
-    };
-    let size_of_len_bytes = match BLOCK_SIZE_BYTES {
-        64 => std::mem::size_of::<u64>(),
-        128 => std::mem::size_of::<u128>(),
-        _ => unreachable!(),
-    };
-    let padding_needed = match lenght % BLOCK_SIZE_BYTES {
-        x if x > BLOCK_SIZE_BYTES - size_of_len_bytes - 1 => {
-            2 * BLOCK_SIZE_BYTES - x - size_of_len_bytes - 1
-        }
-        x => BLOCK_SIZE_BYTES - size_of_len_bytes - x - 1,
+/// Batches of at least this many bytes are split over threads in the `_batch` functions
+const PARALLEL_THRESHOLD: usize = 1 << 16;
+
+/// Pads the last, partial chunk of a message that is `message_len` bytes long.
+/// Gives two blocks and how many of them are used, the second one only if the length does not fit after the 0x80 byte.
+fn sha_pad_last_chunk<const BLOCK_SIZE_BYTES: usize>(
+    last_chunk: &[u8],
+    message_len: u128,
+) -> ([[u8; BLOCK_SIZE_BYTES]; 2], usize) {
+    assert!(last_chunk.len() < BLOCK_SIZE_BYTES);
+    sha_pad_last_bits::<BLOCK_SIZE_BYTES>(last_chunk, message_len * 8)
+}
+
+/// Pads the last, partial chunk of a message that is `message_bit_len` bits long. The bits of
+/// `last_chunk` are taken most significant first and the unused low bits of its last byte are ignored.
+fn sha_pad_last_bits<const BLOCK_SIZE_BYTES: usize>(
+    last_chunk: &[u8],
+    message_bit_len: u128,
+) -> ([[u8; BLOCK_SIZE_BYTES]; 2], usize) {
+    let last_bits = (message_bit_len % (8 * BLOCK_SIZE_BYTES as u128)) as usize;
+    assert_eq!(last_chunk.len(), last_bits.div_ceil(8));
+    // The length takes 64 bits for 64 byte blocks and 128 bits for 128 byte blocks
+    let u128_length: [u8; 16] = message_bit_len.to_be_bytes();
+    let length_bytes = &u128_length[16 - BLOCK_SIZE_BYTES / 8..];
+    assert!(
+        u128_length[..16 - BLOCK_SIZE_BYTES / 8].iter().all(|byte| *byte == 0),
+        "The message is too long for the length field of the padding"
+    );
+    let mut blocks = [[0x00; BLOCK_SIZE_BYTES]; 2];
+    blocks[0][..last_chunk.len()].copy_from_slice(last_chunk);
+    // The 1 bit right after the message
+    let (one_byte, one_bit) = (last_bits / 8, last_bits % 8);
+    blocks[0][one_byte] = (blocks[0][one_byte] & !(0xFF >> one_bit)) | (0x80 >> one_bit);
+    let used_blocks = match one_byte {
+        x if x > BLOCK_SIZE_BYTES - length_bytes.len() - 1 => 2,
+        _ => 1,
     };
-    let iterator = input.chunks_exact(BLOCK_SIZE_BYTES);
-    let last_chunk = iterator.remainder();
-    let padding_bits = [0x00u8].repeat(padding_needed);
-    let pad_chunk: [u8; BLOCK_SIZE_BYTES] =
-        [last_chunk, &[0x80u8][..], &padding_bits[..], length_bytes]
-            .concat()
-            .try_into()
-            .expect("By math :DD");
-    Box::new(
-        iterator
-            .map(|chunk| chunk.try_into().expect("chunk size in BLOCK_SIZE_BYTES"))
-            .chain(once(pad_chunk)),
-    )
+    blocks[used_blocks - 1][BLOCK_SIZE_BYTES - length_bytes.len()..].copy_from_slice(length_bytes);
+    (blocks, used_blocks)
+}
+
+/// Hashes the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+fn sha_bits<STATE, const BLOCK_SIZE_BYTES: usize, const N: usize>(
+    compression: fn(&mut STATE, [u8; BLOCK_SIZE_BYTES]),
+    finalize: fn(STATE) -> [u8; N],
+    IV: STATE,
+    input: &[u8],
+    bit_len: u64,
+) -> [u8; N] {
+    assert!(
+        bit_len <= 8 * input.len() as u64,
+        "The input is shorter than bit_len"
+    );
+    let full_blocks_len = (bit_len / (8 * BLOCK_SIZE_BYTES as u64)) as usize * BLOCK_SIZE_BYTES;
+    let mut state = IV;
+    for chunk in input[..full_blocks_len].chunks_exact(BLOCK_SIZE_BYTES) {
+        compression(&mut state, chunk.try_into().unwrap());
+    }
+    let last_chunk = &input[full_blocks_len..bit_len.div_ceil(8) as usize];
+    let (padding, padding_blocks) =
+        sha_pad_last_bits::<BLOCK_SIZE_BYTES>(last_chunk, u128::from(bit_len));
+    for block in &padding[..padding_blocks] {
+        compression(&mut state, *block);
+    }
+    finalize(state)
 }
 
 fn sha_finalize_32<const N: usize, const T: usize>(state: [u32; N]) -> [u8; T] {
     assert!(N * 32 >= 8 * T);
-    state
-        .into_iter()
-        .flat_map(|x| x.to_be_bytes())
-        .take(T)
-        .collect::<Vec<u8>>()
-        .try_into()
-        .expect("take T u8")
+    let mut output = [0x00; T];
+    for (bytes, word) in output.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes()[..bytes.len()]);
+    }
+    output
 }
 
 fn sha_finalize_64<const N: usize, const T: usize>(state: [u64; N]) -> [u8; T] {
     assert!(N * 64 >= 8 * T);
-    state
-        .into_iter()
-        .flat_map(|x| x.to_be_bytes())
-        .take(T)
-        .collect::<Vec<u8>>()
-        .try_into()
-        .expect("take T u8")
+    let mut output = [0x00; T];
+    for (bytes, word) in output.chunks_mut(8).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes()[..bytes.len()]);
+    }
+    output
+}
+
+/// Hashes every input with `hash` into the output at the same index
+///
+/// ## Panics
+/// if `inputs.len() != outputs.len()`
+fn sha_batch<const N: usize>(
+    hash: fn(&[u8]) -> [u8; N],
+    inputs: &[&[u8]],
+    outputs: &mut [[u8; N]],
+) {
+    assert_eq!(
+        inputs.len(),
+        outputs.len(),
+        "There has to be an output for every input"
+    );
+    let hash_all = |inputs: &[&[u8]], outputs: &mut [[u8; N]]| {
+        for (input, output) in inputs.iter().zip(outputs) {
+            *output = hash(input);
+        }
+    };
+    let total_len: usize = inputs.iter().map(|input| input.len()).sum();
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    if total_len < PARALLEL_THRESHOLD || threads == 1 || inputs.len() < 2 {
+        return hash_all(inputs, outputs);
+    }
+    let per_thread = inputs.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        for (inputs, outputs) in inputs
+            .chunks(per_thread)
+            .zip(outputs.chunks_mut(per_thread))
+        {
+            scope.spawn(move || hash_all(inputs, outputs));
+        }
+    });
 }
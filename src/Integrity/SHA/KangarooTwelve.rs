@@ -0,0 +1,370 @@
+use crate::Integrity::Sponge::Keccak::{keccak_p_1600, Keccak, KeccakReader};
+
+/// The size of the chunks KangarooTwelve splits its input into
+const CHUNK_SIZE: usize = 8192;
+
+/// The TurboSHAKE sponge, Keccak with the 12 round permutation
+///
+/// ## Panics
+/// if `domain_separation` is not in `0x01..=0x7F`
+fn turbo_shake_sponge(rate: usize, domain_separation: u8) -> Keccak {
+    assert!(
+        (0x01..=0x7F).contains(&domain_separation),
+        "The domain separation byte must be in 0x01..=0x7F"
+    );
+    Keccak::with_permutation(keccak_p_1600::<12>, rate, domain_separation)
+}
+
+/// Big endian encoding of `x` without leading zero bytes, followed by the number of bytes
+fn length_encode(x: u64) -> Vec<u8> {
+    let bytes = x.to_be_bytes();
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    [&bytes[leading_zeros..], &[(8 - leading_zeros) as u8]].concat()
+}
+
+macro_rules! turbo_shake_hasher {
+    ($(#[$doc:meta])* $name:ident, $rate:literal) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Keccak,
+        }
+
+        impl $name {
+            /// `0x1F` is the default domain separation byte
+            ///
+            /// ## Panics
+            /// if `domain_separation` is not in `0x01..=0x7F`
+            pub fn new(domain_separation: u8) -> $name {
+                $name {
+                    sponge: turbo_shake_sponge($rate, domain_separation),
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.sponge.update(input)
+            }
+
+            pub fn finalize_xof(self) -> KeccakReader {
+                self.sponge.finalize_xof()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(0x1F)
+            }
+        }
+    };
+}
+
+turbo_shake_hasher!(
+    /// Incremental TurboSHAKE128 (RFC 9861)
+    TurboShake128, 168
+);
+turbo_shake_hasher!(
+    /// Incremental TurboSHAKE256 (RFC 9861)
+    TurboShake256, 136
+);
+
+macro_rules! kangaroo_twelve_hasher {
+    ($(#[$doc:meta])* $name:ident, $rate:literal, $chaining_value_len:literal) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            customization: Vec<u8>,
+            chunk: Vec<u8>,
+            // Only started once the input turns out to be longer than one chunk
+            final_node: Option<Keccak>,
+            leaves: u64,
+        }
+
+        impl $name {
+            pub fn new(customization: &[u8]) -> $name {
+                $name {
+                    customization: customization.to_vec(),
+                    chunk: Vec::with_capacity(CHUNK_SIZE),
+                    final_node: None,
+                    leaves: 0,
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                let mut input = input;
+                while !input.is_empty() {
+                    // A full chunk is only hashed when more input follows, as an input of exactly
+                    // one chunk is not hashed as a tree
+                    if self.chunk.len() == CHUNK_SIZE {
+                        self.hash_chunk();
+                    }
+                    let needed = (CHUNK_SIZE - self.chunk.len()).min(input.len());
+                    self.chunk.extend_from_slice(&input[..needed]);
+                    input = &input[needed..];
+                }
+            }
+
+            fn hash_chunk(&mut self) {
+                match &mut self.final_node {
+                    None => {
+                        let mut final_node = turbo_shake_sponge($rate, 0x06);
+                        final_node.update(&self.chunk);
+                        final_node.update(&[0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+                        self.final_node = Some(final_node);
+                    }
+                    Some(final_node) => {
+                        let mut leaf = turbo_shake_sponge($rate, 0x0B);
+                        leaf.update(&self.chunk);
+                        final_node.update(&leaf.finalize::<$chaining_value_len>());
+                        self.leaves += 1;
+                    }
+                }
+                self.chunk.clear();
+            }
+
+            pub fn finalize_xof(mut self) -> KeccakReader {
+                let customization = std::mem::take(&mut self.customization);
+                self.update(&customization);
+                self.update(&length_encode(customization.len() as u64));
+                if self.final_node.is_none() {
+                    let mut single_node = turbo_shake_sponge($rate, 0x07);
+                    single_node.update(&self.chunk);
+                    return single_node.finalize_xof();
+                }
+                // The length encoding makes sure the last chunk is never empty
+                self.hash_chunk();
+                let mut final_node = self.final_node.unwrap();
+                final_node.update(&length_encode(self.leaves));
+                final_node.update(&[0xFF, 0xFF]);
+                final_node.finalize_xof()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new(b"")
+            }
+        }
+    };
+}
+
+kangaroo_twelve_hasher!(
+    /// Incremental KT128 (RFC 9861), KangarooTwelve on TurboSHAKE128
+    KangarooTwelve128, 168, 32
+);
+kangaroo_twelve_hasher!(
+    /// Incremental KT256 (RFC 9861), KangarooTwelve on TurboSHAKE256
+    KangarooTwelve256, 136, 64
+);
+
+/// TurboSHAKE128 (RFC 9861), SHAKE128 with 12 rounds of Keccak instead of 24
+///
+/// ## Panics
+/// if `domain_separation` is not in `0x01..=0x7F`
+pub fn turbo_shake_128<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    domain_separation: u8,
+) -> [u8; OUTPUT_LEN] {
+    let mut sponge = turbo_shake_sponge(168, domain_separation);
+    sponge.update(input);
+    sponge.finalize::<OUTPUT_LEN>()
+}
+
+/// TurboSHAKE256 (RFC 9861), SHAKE256 with 12 rounds of Keccak instead of 24
+///
+/// ## Panics
+/// if `domain_separation` is not in `0x01..=0x7F`
+pub fn turbo_shake_256<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    domain_separation: u8,
+) -> [u8; OUTPUT_LEN] {
+    let mut sponge = turbo_shake_sponge(136, domain_separation);
+    sponge.update(input);
+    sponge.finalize::<OUTPUT_LEN>()
+}
+
+/// KT128 (RFC 9861). Inputs longer than 8KiB are split into chunks that are hashed independently.
+pub fn kangaroo_twelve_128<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut hasher = KangarooTwelve128::new(customization);
+    hasher.update(input);
+    let mut output = [0x00; OUTPUT_LEN];
+    hasher.finalize_xof().read(&mut output);
+    output
+}
+
+/// KT256 (RFC 9861). Inputs longer than 8KiB are split into chunks that are hashed independently.
+pub fn kangaroo_twelve_256<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut hasher = KangarooTwelve256::new(customization);
+    hasher.update(input);
+    let mut output = [0x00; OUTPUT_LEN];
+    hasher.finalize_xof().read(&mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use const_hex::encode;
+
+    // The repeating pattern 00 01 .. FA of the RFC 9861 test vectors
+    fn ptn(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn turbo_shake_test() {
+        // RFC 9861
+        assert_eq!(
+            encode(turbo_shake_128::<64>(b"", 0x1F)),
+            "1e415f1c5983aff2169217277d17bb538cd945a397ddec541f1ce41af2c1b74c3e8ccae2a4dae56c84a04c2385c03c15e8193bdf58737363321691c05462c8df"
+        );
+        assert_eq!(
+            encode(turbo_shake_128::<32>(&ptn(17), 0x1F)),
+            "9c97d036a3bac819db70ede0ca554ec6e4c2a1a4ffbfd9ec269ca6a111161233"
+        );
+        assert_eq!(
+            encode(turbo_shake_128::<32>(&ptn(17 * 17), 0x1F)),
+            "96c77c279e0126f7fc07c9b07f5cdae1e0be60bdbe10620040e75d7223a624d2"
+        );
+        assert_eq!(
+            encode(turbo_shake_128::<32>(&[0xFF; 3], 0x01)),
+            "bf323f940494e88ee1c540fe660be8a0c93f43d15ec006998462fa994eed5dab"
+        );
+        assert_eq!(
+            encode(turbo_shake_128::<32>(&[0xFF], 0x06)),
+            "8ec9c66465ed0d4a6c35d13506718d687a25cb05c74cca1e42501abd83874a67"
+        );
+        assert_eq!(
+            encode(&turbo_shake_128::<10032>(b"", 0x1F)[10000..]),
+            "a3b9b0385900ce761f22aed548e754da10a5242d62e8c658e3f3a923a7555607"
+        );
+        assert_eq!(
+            encode(turbo_shake_256::<64>(b"", 0x1F)),
+            "367a329dafea871c7802ec67f905ae13c57695dc2c6663c61035f59a18f8e7db11edc0e12e91ea60eb6b32df06dd7f002fbafabb6e13ec1cc20d995547600db0"
+        );
+        assert_eq!(
+            encode(turbo_shake_256::<64>(&ptn(17), 0x1F)),
+            "b3bab0300e6a191fbe6137939835923578794ea54843f5011090fa2f3780a9e5cb22c59d78b40a0fbff9e672c0fbe0970bd2c845091c6044d687054da5d8e9c7"
+        );
+    }
+
+    #[test]
+    fn kangaroo_twelve_128_test() {
+        // RFC 9861
+        assert_eq!(
+            encode(kangaroo_twelve_128::<64>(b"", b"")),
+            "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e54269c056b8c82e48276038b6d292966cc07a3d4645272e31ff38508139eb0a71"
+        );
+        assert_eq!(
+            encode(&kangaroo_twelve_128::<10032>(b"", b"")[10000..]),
+            "e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d"
+        );
+        let expected = [
+            (
+                1,
+                "2bda92450e8b147f8a7cb629e784a058efca7cf7d8218e02d345dfaa65244a1f",
+            ),
+            (
+                17,
+                "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888",
+            ),
+            (
+                17usize.pow(3),
+                "cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0",
+            ),
+            (
+                17usize.pow(4),
+                "8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe",
+            ),
+            (
+                17usize.pow(5),
+                "844d610933b1b9963cbdeb5ae3b6b05cc7cbd67ceedf883eb678a0a8e0371682",
+            ),
+        ];
+        for (len, hash) in expected {
+            assert_eq!(encode(kangaroo_twelve_128::<32>(&ptn(len), b"")), hash);
+        }
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(b"", &ptn(1))),
+            "fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(&[0xFF], &ptn(41))),
+            "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(&[0xFF; 3], &ptn(41 * 41))),
+            "c389e5009ae57120854c2e8c64670ac01358cf4c1baf89447a724234dc7ced74"
+        );
+        // Around the chunk size
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(&ptn(8191), b"")),
+            "1b577636f723643e990cc7d6a659837436fd6a103626600eb8301cd1dbe553d6"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(&ptn(8192), b"")),
+            "48f256f6772f9edfb6a8b661ec92dc93b95ebd05a08a17b39ae3490870c926c3"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(&ptn(8192), &ptn(8189))),
+            "3ed12f70fb05ddb58689510ab3e4d23c6c6033849aa01e1d8c220a297fedcd0b"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_128::<32>(&ptn(8192), &ptn(8190))),
+            "6a7c1b6a5cd0d8c9ca943a4a216cc64604559a2ea45f78570a15253d67ba00ae"
+        );
+    }
+
+    #[test]
+    fn kangaroo_twelve_256_test() {
+        // RFC 9861
+        assert_eq!(
+            encode(kangaroo_twelve_256::<64>(b"", b"")),
+            "b23d2e9cea9f4904e02bec06817fc10ce38ce8e93ef4c89e6537076af8646404e3e8b68107b8833a5d30490aa33482353fd4adc7148ecb782855003aaebde4a9"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_256::<64>(&ptn(17usize.pow(4)), b"")),
+            "b06275d284cd1cf205bcbe57dccd3ec1ff6686e3ed15776383e1f2fa3c6ac8f08bf8a162829db1a44b2a43ff83dd89c3cf1ceb61ede659766d5ccf817a62ba8d"
+        );
+        assert_eq!(
+            encode(kangaroo_twelve_256::<64>(&ptn(8192), &ptn(8190))),
+            "f4b5908b929ffe01e0f79ec2f21243d41a396b2e7303a6af1d6399cd6c7a0a2dd7c4f607e8277f9c9b1cb4ab9ddc59d4b92d1fc7558441f1832c3279a4241b8b"
+        );
+    }
+
+    #[test]
+    fn kangaroo_twelve_incremental_test() {
+        let message = ptn(3 * CHUNK_SIZE + 100);
+        for len in [
+            0,
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            2 * CHUNK_SIZE,
+            message.len(),
+        ] {
+            let input = &message[..len];
+            for piece_size in [1000, CHUNK_SIZE, CHUNK_SIZE + 7] {
+                let mut hasher = KangarooTwelve128::new(b"custom");
+                for piece in input.chunks(piece_size) {
+                    hasher.update(piece);
+                }
+                let mut output = [0x00; 40];
+                hasher.finalize_xof().read(&mut output);
+                assert_eq!(output, kangaroo_twelve_128::<40>(input, b"custom"));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn turbo_shake_domain_separation_test() {
+        let _ = turbo_shake_128::<32>(b"", 0x80);
+    }
+}
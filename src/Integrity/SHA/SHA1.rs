@@ -1,5 +1,8 @@
-use super::sha_padding;
-use crate::Integrity::Merkle_Damgard::merkle_damgard;
+use super::{sha_batch, sha_bits, sha_finalize_32, sha_pad_last_chunk, Midstate};
+use crate::Integrity::Digest;
+use crate::Integrity::Merkle_Damgard::{merkle_damgard, MerkleDamgard};
+
+const SHA1_IV: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
 
 fn sha_1_constants(i: usize, b: u32, c: u32, d: u32) -> (u32, u32) {
     match i {
@@ -40,20 +43,100 @@ fn sha1_comp_fun(state: &mut [u32; 5], chunk: [u8; 64]) {
 }
 
 fn sha1_finalize(state: [u32; 5]) -> [u8; 20] {
-    state
-        .map(|x| x.to_be_bytes())
-        .concat()
-        .try_into()
-        .expect("5*32 should be 160/8")
+    sha_finalize_32::<5, 20>(state)
 }
 
 pub fn sha1(input: &[u8]) -> [u8; 20] {
-    let IV: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
     merkle_damgard::<[u32; 5], _, _, _, 20, 64>(
-        sha_padding::<64>,
+        sha_pad_last_chunk::<64>,
         sha1_comp_fun,
         sha1_finalize,
-        IV,
+        SHA1_IV,
         input,
     )
 }
+
+/// SHA1 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn sha1_bits(input: &[u8], bit_len: u64) -> [u8; 20] {
+    sha_bits(sha1_comp_fun, sha1_finalize, SHA1_IV, input, bit_len)
+}
+
+/// Hashes many messages, `outputs[i]` gets the SHA1 digest of `inputs[i]`. Large batches are split over threads.
+///
+/// ## Panics
+/// if `inputs.len() != outputs.len()`
+pub fn sha1_batch(inputs: &[&[u8]], outputs: &mut [[u8; 20]]) {
+    sha_batch(sha1, inputs, outputs)
+}
+
+/// Incremental SHA1 hasher. Feed the message in pieces with `update` and get the digest with `finalize`.
+/// Gives the same digest as `sha1` on the concatenated input.
+#[derive(Clone)]
+pub struct Sha1 {
+    hasher: MerkleDamgard<[u32; 5], 64>,
+}
+
+impl Sha1 {
+    pub fn new() -> Sha1 {
+        Sha1 {
+            hasher: MerkleDamgard::new(SHA1_IV, sha1_comp_fun),
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.hasher.update(input)
+    }
+
+    /// The chaining state after the input so far, `None` if the input is not a whole number of blocks
+    pub fn midstate(&self) -> Option<Midstate<[u32; 5]>> {
+        self.hasher
+            .midstate()
+            .map(|(state, byte_count)| Midstate { state, byte_count })
+    }
+
+    /// Resumes hashing from a midstate
+    ///
+    /// ## Panics
+    /// if `midstate.byte_count` is not a multiple of 64<br>
+    /// if `midstate.byte_count >= 2^61`, the padding can't encode the length in bits
+    pub fn from_midstate(midstate: Midstate<[u32; 5]>) -> Sha1 {
+        Sha1 {
+            hasher: MerkleDamgard::from_midstate(
+                midstate.state,
+                midstate.byte_count,
+                sha1_comp_fun,
+            ),
+        }
+    }
+
+    pub fn finalize(self) -> [u8; 20] {
+        sha1_finalize(self.hasher.finalize(sha_pad_last_chunk::<64>))
+    }
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Digest for Sha1 {
+    const OUTPUT_SIZE: usize = 20;
+    const BLOCK_SIZE: usize = 64;
+    type Output = [u8; 20];
+
+    fn new() -> Self {
+        Sha1::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Sha1::update(self, input)
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha1::finalize(self)
+    }
+}
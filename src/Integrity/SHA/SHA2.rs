@@ -1,6 +1,7 @@
-use crate::Integrity::Merkle_Damgard::merkle_damgard;
+use crate::Integrity::Digest;
+use crate::Integrity::Merkle_Damgard::{merkle_damgard, MerkleDamgard};
 use super::*;
-
+use std::sync::OnceLock;
 
 fn sha256_comp_fun(state: &mut [u32; 8], chunk: [u8; 64]) {
     let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [u32; 8] = *state;
@@ -48,42 +49,52 @@ fn sha256_comp_fun(state: &mut [u32; 8], chunk: [u8; 64]) {
     }
 }
 
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SHA224_IV: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
 pub fn sha256(input: &[u8]) -> [u8; 32] {
-    let IV = [
-        0x6a09e667u32,
-        0xbb67ae85u32,
-        0x3c6ef372u32,
-        0xa54ff53au32,
-        0x510e527fu32,
-        0x9b05688cu32,
-        0x1f83d9abu32,
-        0x5be0cd19u32,
-    ];
     merkle_damgard::<[u32; 8], _, _, _, 32, 64>(
-        sha_padding::<64>,
+        sha_pad_last_chunk::<64>,
         sha256_comp_fun,
         sha_finalize_32::<8, 32>,
-        IV,
+        SHA256_IV,
         input,
     )
 }
 
 pub fn sha224(input: &[u8]) -> [u8; 28] {
-    let IV = [
-        0xc1059ed8u32,
-        0x367cd507,
-        0x3070dd17,
-        0xf70e5939,
-        0xffc00b31,
-        0x68581511,
-        0x64f98fa7,
-        0xbefa4fa4,
-    ];
     merkle_damgard::<[u32; 8], _, _, _, 28, 64>(
-        sha_padding::<64>,
+        sha_pad_last_chunk::<64>,
         sha256_comp_fun,
         sha_finalize_32::<8, 28>,
-        IV,
+        SHA224_IV,
         input,
     )
 }
@@ -136,58 +147,29 @@ fn sha512_comp_fun(state: &mut [u64; 8], chunk: [u8; 128]) {
 
 
 pub fn sha512(input: &[u8]) -> [u8; 64] {
-    let IV = [
-        0x6a09e667f3bcc908u64,
-        0xbb67ae8584caa73bu64,
-        0x3c6ef372fe94f82bu64,
-        0xa54ff53a5f1d36f1u64,
-        0x510e527fade682d1u64,
-        0x9b05688c2b3e6c1fu64,
-        0x1f83d9abfb41bd6bu64,
-        0x5be0cd19137e2179u64,
-    ];
     merkle_damgard::<[u64; 8], _, _, _, 64, 128>(
-        sha_padding::<128>,
+        sha_pad_last_chunk::<128>,
         sha512_comp_fun,
         sha_finalize_64::<8, 64>,
-        IV,
+        SHA512_IV,
         input,
     )
 }
 
 pub fn sha384(input: &[u8]) -> [u8; 48] {
-    let IV = [
-        0xcbbb9d5dc1059ed8,
-        0x629a292a367cd507,
-        0x9159015a3070dd17,
-        0x152fecd8f70e5939,
-        0x67332667ffc00b31,
-        0x8eb44a8768581511,
-        0xdb0c2e0d64f98fa7,
-        0x47b5481dbefa4fa4,
-    ];
     merkle_damgard::<[u64; 8], _, _, _, 48, 128>(
-        sha_padding::<128>,
+        sha_pad_last_chunk::<128>,
         sha512_comp_fun,
         sha_finalize_64::<8, 48>,
-        IV,
+        SHA384_IV,
         input,
     )
 }
 
 fn sha512_IV_generating_function(input: &[u8]) -> [u8; 64] {
-    let IV = [
-        0x6a09e667f3bcc908u64 ^ 0xa5a5a5a5a5a5a5a5,
-        0xbb67ae8584caa73bu64 ^ 0xa5a5a5a5a5a5a5a5,
-        0x3c6ef372fe94f82bu64 ^ 0xa5a5a5a5a5a5a5a5,
-        0xa54ff53a5f1d36f1u64 ^ 0xa5a5a5a5a5a5a5a5,
-        0x510e527fade682d1u64 ^ 0xa5a5a5a5a5a5a5a5,
-        0x9b05688c2b3e6c1fu64 ^ 0xa5a5a5a5a5a5a5a5,
-        0x1f83d9abfb41bd6bu64 ^ 0xa5a5a5a5a5a5a5a5,
-        0x5be0cd19137e2179u64 ^ 0xa5a5a5a5a5a5a5a5,
-    ];
+    let IV = SHA512_IV.map(|x| x ^ 0xa5a5a5a5a5a5a5a5);
     merkle_damgard::<[u64; 8], _, _, _, 64, 128>(
-        sha_padding,
+        sha_pad_last_chunk,
         sha512_comp_fun,
         sha_finalize_64::<8, 64>,
         IV,
@@ -195,25 +177,93 @@ fn sha512_IV_generating_function(input: &[u8]) -> [u8; 64] {
     )
 }
 
+/// The IVs of SHA-512/t, computed the first time they are needed
+static SHA512_T_IVS: [OnceLock<[u64; 8]>; 512] = [const { OnceLock::new() }; 512];
+
+/// The IV of SHA-512/t for `t` bits of output
+///
+/// ## Panics
+/// if `t == 0`, `t >= 512` or `t == 384`
+fn sha512_t_IV(t: usize) -> [u64; 8] {
+    assert!(0 < t && t < 512, "t has to be between 1 and 511");
+    assert_ne!(t, 384, "SHA-512/384 is not allowed, use SHA384");
+    *SHA512_T_IVS[t].get_or_init(|| {
+        // The name "SHA-512/t" with t in decimal, written out without a String
+        let mut name = [0x00; 11];
+        name[..8].copy_from_slice(b"SHA-512/");
+        let mut name_len = 8;
+        for divisor in [100, 10, 1] {
+            if t >= divisor || divisor == 1 {
+                name[name_len] = b'0' + (t / divisor % 10) as u8;
+                name_len += 1;
+            }
+        }
+        let hash = sha512_IV_generating_function(&name[..name_len]);
+        let mut IV = [0u64; 8];
+        for (word, bytes) in IV.iter_mut().zip(hash.chunks_exact(8)) {
+            *word = u64::from_be_bytes(bytes.try_into().unwrap());
+        }
+        IV
+    })
+}
 
 fn sha512_to_t<const T_BYTES: usize>(input: &[u8]) -> [u8; T_BYTES] {
-    assert!(T_BYTES * 8 < 512, "t cannot be >= 512");
-    let length = T_BYTES * 8;
-    let IV: [u64; 8] = sha512_IV_generating_function(format!("SHA-512/{}", length).as_bytes())
-        .chunks(8)
-        .map(|x| u64::from_be_bytes((*x).try_into().unwrap()))
-        .collect::<Vec<u64>>()
-        .try_into()
-        .unwrap();
     merkle_damgard::<[u64; 8], _, _, _, T_BYTES, 128>(
-        sha_padding,
+        sha_pad_last_chunk,
         sha512_comp_fun,
         sha_finalize_64::<8, T_BYTES>,
-        IV,
+        sha512_t_IV(T_BYTES * 8),
         input,
     )
 }
 
+/// The first `t` bits of a SHA512 digest, the unused low bits of the last byte are zero
+fn sha512_t_output(digest: [u8; 64], t: usize) -> Vec<u8> {
+    let mut output = digest[..t.div_ceil(8)].to_vec();
+    if !t.is_multiple_of(8) {
+        *output.last_mut().unwrap() &= 0xFF << (8 - t % 8);
+    }
+    output
+}
+
+/// SHA-512/t (FIPS 180-4), SHA512 with its own IV truncated to `t` bits. The output is `t` bits in
+/// `ceil(t / 8)` bytes, when `t` is not a multiple of 8 the unused low bits of the last byte are zero.
+///
+/// ## Panics
+/// if `t == 0`, `t >= 512` or `t == 384`
+pub fn sha512_t(input: &[u8], t: usize) -> Vec<u8> {
+    let mut hasher = Sha512T::new(t);
+    hasher.update(input);
+    hasher.finalize()
+}
+
+/// Incremental SHA-512/t hasher, gives the same digest as `sha512_t`
+#[derive(Clone)]
+pub struct Sha512T {
+    hasher: MerkleDamgard<[u64; 8], 128>,
+    t: usize,
+}
+
+impl Sha512T {
+    /// ## Panics
+    /// if `t == 0`, `t >= 512` or `t == 384`
+    pub fn new(t: usize) -> Sha512T {
+        Sha512T {
+            hasher: MerkleDamgard::new(sha512_t_IV(t), sha512_comp_fun),
+            t,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.hasher.update(input)
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let state = self.hasher.finalize(sha_pad_last_chunk::<128>);
+        sha512_t_output(sha_finalize_64::<8, 64>(state), self.t)
+    }
+}
+
 pub fn sha512_to_224(input: &[u8]) -> [u8; 28] {
     sha512_to_t::<28>(input)
 }
@@ -222,6 +272,188 @@ pub fn sha512_to_256(input: &[u8]) -> [u8; 32] {
     sha512_to_t::<32>(input)
 }
 
+macro_rules! sha2_bits {
+    ($(#[$doc:meta])* $name:ident, $comp_fun:ident, $finalize:expr, $IV:expr, $hash_len:literal) => {
+        $(#[$doc])*
+        ///
+        /// ## Panics
+        /// if `bit_len > 8 * input.len()`
+        pub fn $name(input: &[u8], bit_len: u64) -> [u8; $hash_len] {
+            sha_bits($comp_fun, $finalize, $IV, input, bit_len)
+        }
+    };
+}
+
+sha2_bits!(
+    /// SHA224 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+    sha224_bits, sha256_comp_fun, sha_finalize_32::<8, 28>, SHA224_IV, 28
+);
+sha2_bits!(
+    /// SHA256 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+    sha256_bits, sha256_comp_fun, sha_finalize_32::<8, 32>, SHA256_IV, 32
+);
+sha2_bits!(
+    /// SHA384 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+    sha384_bits, sha512_comp_fun, sha_finalize_64::<8, 48>, SHA384_IV, 48
+);
+sha2_bits!(
+    /// SHA512 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+    sha512_bits, sha512_comp_fun, sha_finalize_64::<8, 64>, SHA512_IV, 64
+);
+sha2_bits!(
+    /// SHA512/224 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+    sha512_to_224_bits, sha512_comp_fun, sha_finalize_64::<8, 28>, sha512_t_IV(224), 28
+);
+sha2_bits!(
+    /// SHA512/256 of the first `bit_len` bits of `input`, the bits of every byte are taken most significant first
+    sha512_to_256_bits, sha512_comp_fun, sha_finalize_64::<8, 32>, sha512_t_IV(256), 32
+);
+
+/// SHA-512/t of the first `bit_len` bits of `input`, the bits of every byte are taken most
+/// significant first. The output is laid out as in `sha512_t`.
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`, `t == 0`, `t >= 512` or `t == 384`
+pub fn sha512_t_bits(input: &[u8], bit_len: u64, t: usize) -> Vec<u8> {
+    let digest = sha_bits(
+        sha512_comp_fun,
+        sha_finalize_64::<8, 64>,
+        sha512_t_IV(t),
+        input,
+        bit_len,
+    );
+    sha512_t_output(digest, t)
+}
+
+macro_rules! sha2_batch {
+    ($(#[$doc:meta])* $name:ident, $hash:ident, $hash_len:literal) => {
+        $(#[$doc])*
+        ///
+        /// ## Panics
+        /// if `inputs.len() != outputs.len()`
+        pub fn $name(inputs: &[&[u8]], outputs: &mut [[u8; $hash_len]]) {
+            sha_batch($hash, inputs, outputs)
+        }
+    };
+}
+
+sha2_batch!(
+    /// Hashes many messages, `outputs[i]` gets the SHA224 digest of `inputs[i]`. Large batches are split over threads.
+    sha224_batch, sha224, 28
+);
+sha2_batch!(
+    /// Hashes many messages, `outputs[i]` gets the SHA256 digest of `inputs[i]`. Large batches are split over threads.
+    sha256_batch, sha256, 32
+);
+sha2_batch!(
+    /// Hashes many messages, `outputs[i]` gets the SHA384 digest of `inputs[i]`. Large batches are split over threads.
+    sha384_batch, sha384, 48
+);
+sha2_batch!(
+    /// Hashes many messages, `outputs[i]` gets the SHA512 digest of `inputs[i]`. Large batches are split over threads.
+    sha512_batch, sha512, 64
+);
+
+/// Generates an incremental hasher struct with `new`, `update` and `finalize` for a SHA2 variant
+macro_rules! sha2_hasher {
+    ($(#[$doc:meta])* $name:ident, $state:ty, $block_size:literal, $hash_len:literal, $comp_fun:ident, $finalize:expr, $IV:expr) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        #[allow(non_camel_case_types)]
+        pub struct $name {
+            hasher: MerkleDamgard<$state, $block_size>,
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    hasher: MerkleDamgard::new($IV, $comp_fun),
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.hasher.update(input)
+            }
+
+            /// The chaining state after the input so far, `None` if the input is not a whole number of blocks
+            pub fn midstate(&self) -> Option<Midstate<$state>> {
+                self.hasher
+                    .midstate()
+                    .map(|(state, byte_count)| Midstate { state, byte_count })
+            }
+
+            /// Resumes hashing from a midstate
+            ///
+            /// ## Panics
+            /// if `midstate.byte_count` is not a multiple of the block size<br>
+            /// if `midstate.byte_count >= 2^61` for 64 byte blocks or `>= 2^125` for 128 byte
+            /// blocks, the padding can't encode the length in bits
+            pub fn from_midstate(midstate: Midstate<$state>) -> $name {
+                $name {
+                    hasher: MerkleDamgard::from_midstate(
+                        midstate.state,
+                        midstate.byte_count,
+                        $comp_fun,
+                    ),
+                }
+            }
+
+            pub fn finalize(self) -> [u8; $hash_len] {
+                $finalize(self.hasher.finalize(sha_pad_last_chunk::<$block_size>))
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Digest for $name {
+            const OUTPUT_SIZE: usize = $hash_len;
+            const BLOCK_SIZE: usize = $block_size;
+            type Output = [u8; $hash_len];
+
+            fn new() -> Self {
+                $name::new()
+            }
+
+            fn update(&mut self, input: &[u8]) {
+                $name::update(self, input)
+            }
+
+            fn finalize(self) -> Self::Output {
+                $name::finalize(self)
+            }
+        }
+    };
+}
+
+sha2_hasher!(
+    /// Incremental SHA224 hasher, gives the same digest as `sha224`
+    Sha224, [u32; 8], 64, 28, sha256_comp_fun, sha_finalize_32::<8, 28>, SHA224_IV
+);
+sha2_hasher!(
+    /// Incremental SHA256 hasher, gives the same digest as `sha256`
+    Sha256, [u32; 8], 64, 32, sha256_comp_fun, sha_finalize_32::<8, 32>, SHA256_IV
+);
+sha2_hasher!(
+    /// Incremental SHA384 hasher, gives the same digest as `sha384`
+    Sha384, [u64; 8], 128, 48, sha512_comp_fun, sha_finalize_64::<8, 48>, SHA384_IV
+);
+sha2_hasher!(
+    /// Incremental SHA512 hasher, gives the same digest as `sha512`
+    Sha512, [u64; 8], 128, 64, sha512_comp_fun, sha_finalize_64::<8, 64>, SHA512_IV
+);
+sha2_hasher!(
+    /// Incremental SHA512/224 hasher, gives the same digest as `sha512_to_224`
+    Sha512_224, [u64; 8], 128, 28, sha512_comp_fun, sha_finalize_64::<8, 28>, sha512_t_IV(224)
+);
+sha2_hasher!(
+    /// Incremental SHA512/256 hasher, gives the same digest as `sha512_to_256`
+    Sha512_256, [u64; 8], 128, 32, sha512_comp_fun, sha_finalize_64::<8, 32>, sha512_t_IV(256)
+);
+
 const SHA256_K: [u32; 64] = [
     0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
     0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
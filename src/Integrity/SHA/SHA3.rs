@@ -1,4 +1,7 @@
-use crate::Integrity::Sponge::Keccak::keccak;
+use crate::Integrity::Digest;
+use crate::Integrity::Sponge::Keccak::{
+    keccak, keccak_bits, keccak_bits_xof, Keccak, KeccakReader,
+};
 
 pub fn sha_3_244(input: &[u8]) -> [u8; 28] {
     keccak::<28>(input, 144, 0x06)
@@ -16,6 +19,26 @@ pub fn sha_3_512(input: &[u8]) -> [u8; 64] {
     keccak::<64>(input, 72, 0x06)
 }
 
+/// The original Keccak-224 from before FIPS 202, padded with the 0x01 suffix instead of 0x06
+pub fn keccak_224(input: &[u8]) -> [u8; 28] {
+    keccak::<28>(input, 144, 0x01)
+}
+
+/// The original Keccak-256 from before FIPS 202, as used by Ethereum
+pub fn keccak_256(input: &[u8]) -> [u8; 32] {
+    keccak::<32>(input, 136, 0x01)
+}
+
+/// The original Keccak-384 from before FIPS 202
+pub fn keccak_384(input: &[u8]) -> [u8; 48] {
+    keccak::<48>(input, 104, 0x01)
+}
+
+/// The original Keccak-512 from before FIPS 202
+pub fn keccak_512(input: &[u8]) -> [u8; 64] {
+    keccak::<64>(input, 72, 0x01)
+}
+
 pub fn shake_128<const OUTPUT_LEN: usize>(input: &[u8]) -> [u8; OUTPUT_LEN] {
     keccak::<OUTPUT_LEN>(input, 168, 0x1F)
 }
@@ -23,3 +46,196 @@ pub fn shake_128<const OUTPUT_LEN: usize>(input: &[u8]) -> [u8; OUTPUT_LEN] {
 pub fn shake_256<const OUTPUT_LEN: usize>(input: &[u8]) -> [u8; OUTPUT_LEN] {
     keccak::<OUTPUT_LEN>(input, 136, 0x1F)
 }
+
+/// SHA3-224 of the first `bit_len` bits of `input`, the bits of every byte are taken least significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn sha_3_224_bits(input: &[u8], bit_len: u64) -> [u8; 28] {
+    keccak_bits::<28>(input, bit_len, 144, 0x06)
+}
+
+/// SHA3-256 of the first `bit_len` bits of `input`, the bits of every byte are taken least significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn sha_3_256_bits(input: &[u8], bit_len: u64) -> [u8; 32] {
+    keccak_bits::<32>(input, bit_len, 136, 0x06)
+}
+
+/// SHA3-384 of the first `bit_len` bits of `input`, the bits of every byte are taken least significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn sha_3_384_bits(input: &[u8], bit_len: u64) -> [u8; 48] {
+    keccak_bits::<48>(input, bit_len, 104, 0x06)
+}
+
+/// SHA3-512 of the first `bit_len` bits of `input`, the bits of every byte are taken least significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn sha_3_512_bits(input: &[u8], bit_len: u64) -> [u8; 64] {
+    keccak_bits::<64>(input, bit_len, 72, 0x06)
+}
+
+/// SHAKE128 of the first `bit_len` bits of `input`, the bits of every byte are taken least significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn shake_128_bits<const OUTPUT_LEN: usize>(input: &[u8], bit_len: u64) -> [u8; OUTPUT_LEN] {
+    keccak_bits::<OUTPUT_LEN>(input, bit_len, 168, 0x1F)
+}
+
+/// SHAKE256 of the first `bit_len` bits of `input`, the bits of every byte are taken least significant first
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn shake_256_bits<const OUTPUT_LEN: usize>(input: &[u8], bit_len: u64) -> [u8; OUTPUT_LEN] {
+    keccak_bits::<OUTPUT_LEN>(input, bit_len, 136, 0x1F)
+}
+
+/// SHAKE128 of the first `bit_len` bits of `input` with an arbitrary amount of output
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn shake_128_bits_xof(input: &[u8], bit_len: u64) -> KeccakReader {
+    keccak_bits_xof(input, bit_len, 168, 0x1F)
+}
+
+/// SHAKE256 of the first `bit_len` bits of `input` with an arbitrary amount of output
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn shake_256_bits_xof(input: &[u8], bit_len: u64) -> KeccakReader {
+    keccak_bits_xof(input, bit_len, 136, 0x1F)
+}
+
+/// Generates an incremental hasher struct with `new`, `update` and `finalize` for a SHA3 or legacy Keccak variant
+macro_rules! sha3_hasher {
+    ($(#[$doc:meta])* $name:ident, $rate:literal, $hash_len:literal, $suffix:literal) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        #[allow(non_camel_case_types)]
+        pub struct $name {
+            sponge: Keccak,
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    sponge: Keccak::new($rate, $suffix),
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.sponge.update(input)
+            }
+
+            pub fn finalize(self) -> [u8; $hash_len] {
+                self.sponge.finalize::<$hash_len>()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Digest for $name {
+            const OUTPUT_SIZE: usize = $hash_len;
+            const BLOCK_SIZE: usize = $rate;
+            type Output = [u8; $hash_len];
+
+            fn new() -> Self {
+                $name::new()
+            }
+
+            fn update(&mut self, input: &[u8]) {
+                $name::update(self, input)
+            }
+
+            fn finalize(self) -> Self::Output {
+                $name::finalize(self)
+            }
+        }
+    };
+}
+
+sha3_hasher!(
+    /// Incremental SHA3-224 hasher, gives the same digest as `sha_3_244`
+    Sha3_224, 144, 28, 0x06
+);
+sha3_hasher!(
+    /// Incremental SHA3-256 hasher, gives the same digest as `sha_3_256`
+    Sha3_256, 136, 32, 0x06
+);
+sha3_hasher!(
+    /// Incremental SHA3-384 hasher, gives the same digest as `sha_3_384`
+    Sha3_384, 104, 48, 0x06
+);
+sha3_hasher!(
+    /// Incremental SHA3-512 hasher, gives the same digest as `sha_3_512`
+    Sha3_512, 72, 64, 0x06
+);
+
+sha3_hasher!(
+    /// Incremental legacy Keccak-224 hasher, gives the same digest as `keccak_224`
+    Keccak224, 144, 28, 0x01
+);
+sha3_hasher!(
+    /// Incremental legacy Keccak-256 hasher, gives the same digest as `keccak_256`
+    Keccak256, 136, 32, 0x01
+);
+sha3_hasher!(
+    /// Incremental legacy Keccak-384 hasher, gives the same digest as `keccak_384`
+    Keccak384, 104, 48, 0x01
+);
+sha3_hasher!(
+    /// Incremental legacy Keccak-512 hasher, gives the same digest as `keccak_512`
+    Keccak512, 72, 64, 0x01
+);
+
+/// Generates an incremental extendable output struct with `new`, `update` and `finalize_xof` for a SHAKE variant
+macro_rules! shake_hasher {
+    ($(#[$doc:meta])* $name:ident, $rate:literal) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Keccak,
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    sponge: Keccak::new($rate, 0x1F),
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.sponge.update(input)
+            }
+
+            /// Finish absorbing and get a reader, which gives as much output as is read from it
+            pub fn finalize_xof(self) -> KeccakReader {
+                self.sponge.finalize_xof()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+}
+
+shake_hasher!(
+    /// Incremental SHAKE128, the first `OUTPUT_LEN` bytes read are the same as `shake_128::<OUTPUT_LEN>`
+    Shake128, 168
+);
+shake_hasher!(
+    /// Incremental SHAKE256, the first `OUTPUT_LEN` bytes read are the same as `shake_256::<OUTPUT_LEN>`
+    Shake256, 136
+);
@@ -0,0 +1,505 @@
+use crate::Authenticity::AuthenticationError;
+use crate::Integrity::Sponge::Keccak::{Keccak, KeccakReader};
+use crate::Integrity::HMAC::constant_time_eq;
+
+/// The shortest KMAC tag in bytes that `verify` accepts, SP 800-185 doesn't allow less than 32 bits
+pub const KMAC_MIN_TAG_LEN: usize = 4;
+
+/// Big endian encoding of `x` with the number of bytes in front
+fn left_encode(x: u64) -> Vec<u8> {
+    let bytes = x.to_be_bytes();
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    [&[(8 - leading_zeros) as u8], &bytes[leading_zeros..]].concat()
+}
+
+/// Big endian encoding of `x` with the number of bytes behind
+fn right_encode(x: u64) -> Vec<u8> {
+    let bytes = x.to_be_bytes();
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count().min(7);
+    [&bytes[leading_zeros..], &[(8 - leading_zeros) as u8]].concat()
+}
+
+/// The string with its length in bits in front, so that concatenated strings can be told apart
+fn encode_string(string: &[u8]) -> Vec<u8> {
+    [left_encode(string.len() as u64 * 8), string.to_vec()].concat()
+}
+
+/// Prepends the encoding of `rate` and pads with zeros to a multiple of `rate` bytes
+fn bytepad(input: &[u8], rate: usize) -> Vec<u8> {
+    let mut padded = [left_encode(rate as u64), input.to_vec()].concat();
+    padded.resize(padded.len().div_ceil(rate) * rate, 0x00);
+    padded
+}
+
+/// The Keccak sponge of cSHAKE with the function name and customization string absorbed
+fn cshake_sponge(rate: usize, function_name: &[u8], customization: &[u8]) -> Keccak {
+    // Without a function name and customization string cSHAKE is SHAKE
+    if function_name.is_empty() && customization.is_empty() {
+        return Keccak::new(rate, 0x1F);
+    }
+    let mut sponge = Keccak::new(rate, 0x04);
+    sponge.update(&bytepad(
+        &[encode_string(function_name), encode_string(customization)].concat(),
+        rate,
+    ));
+    sponge
+}
+
+macro_rules! cshake_hasher {
+    ($(#[$doc:meta])* $name:ident, $rate:literal) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Keccak,
+        }
+
+        impl $name {
+            /// `function_name` is reserved for functions defined by NIST, use the customization string to separate domains
+            pub fn new(function_name: &[u8], customization: &[u8]) -> $name {
+                $name {
+                    sponge: cshake_sponge($rate, function_name, customization),
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.sponge.update(input)
+            }
+
+            pub fn finalize<const OUTPUT_LEN: usize>(self) -> [u8; OUTPUT_LEN] {
+                self.sponge.finalize::<OUTPUT_LEN>()
+            }
+
+            pub fn finalize_xof(self) -> KeccakReader {
+                self.sponge.finalize_xof()
+            }
+        }
+    };
+}
+
+cshake_hasher!(
+    /// Incremental cSHAKE128, SHAKE128 with a function name and customization string
+    CShake128, 168
+);
+cshake_hasher!(
+    /// Incremental cSHAKE256, SHAKE256 with a function name and customization string
+    CShake256, 136
+);
+
+macro_rules! kmac {
+    ($(#[$doc:meta])* $name:ident, $cshake:ident, $rate:literal) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name {
+            cshake: $cshake,
+        }
+
+        impl $name {
+            pub fn new(key: &[u8], customization: &[u8]) -> $name {
+                let mut cshake = $cshake::new(b"KMAC", customization);
+                cshake.update(&bytepad(&encode_string(key), $rate));
+                $name { cshake }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.cshake.update(input)
+            }
+
+            /// The tag depends on its length, so a shorter tag is not a prefix of a longer one
+            pub fn finalize<const OUTPUT_LEN: usize>(mut self) -> [u8; OUTPUT_LEN] {
+                self.cshake.update(&right_encode(OUTPUT_LEN as u64 * 8));
+                self.cshake.finalize::<OUTPUT_LEN>()
+            }
+
+            /// The XOF form, where the output does not depend on how much is read
+            pub fn finalize_xof(mut self) -> KeccakReader {
+                self.cshake.update(&right_encode(0));
+                self.cshake.finalize_xof()
+            }
+
+            /// Checks a tag of `OUTPUT_LEN` bytes against the message in constant time. The
+            /// length is set by the verifier and not taken from the tag
+            ///
+            /// ## Errors
+            /// `TagNotMatchMessage` if the tag does not match or isn't `OUTPUT_LEN` bytes long<br>
+            /// `TagNotMatchMessage` if `OUTPUT_LEN < KMAC_MIN_TAG_LEN`
+            pub fn verify<const OUTPUT_LEN: usize>(
+                self,
+                tag: &[u8],
+            ) -> Result<(), AuthenticationError> {
+                if OUTPUT_LEN < KMAC_MIN_TAG_LEN {
+                    return Err(AuthenticationError::TagNotMatchMessage);
+                }
+                match constant_time_eq(&self.finalize::<OUTPUT_LEN>(), tag) {
+                    true => Ok(()),
+                    false => Err(AuthenticationError::TagNotMatchMessage),
+                }
+            }
+        }
+    };
+}
+
+kmac!(
+    /// Incremental KMAC128, the Keccak based MAC
+    Kmac128, CShake128, 168
+);
+kmac!(
+    /// Incremental KMAC256, the Keccak based MAC
+    Kmac256, CShake256, 136
+);
+
+/// cSHAKE128 (NIST SP 800-185)
+pub fn cshake_128<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    function_name: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut cshake = CShake128::new(function_name, customization);
+    cshake.update(input);
+    cshake.finalize()
+}
+
+/// cSHAKE256 (NIST SP 800-185)
+pub fn cshake_256<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    function_name: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut cshake = CShake256::new(function_name, customization);
+    cshake.update(input);
+    cshake.finalize()
+}
+
+/// KMAC128 (NIST SP 800-185), a MAC of OUTPUT_LEN bytes
+pub fn kmac_128<const OUTPUT_LEN: usize>(
+    key: &[u8],
+    input: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut kmac = Kmac128::new(key, customization);
+    kmac.update(input);
+    kmac.finalize()
+}
+
+/// KMAC256 (NIST SP 800-185), a MAC of OUTPUT_LEN bytes
+pub fn kmac_256<const OUTPUT_LEN: usize>(
+    key: &[u8],
+    input: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut kmac = Kmac256::new(key, customization);
+    kmac.update(input);
+    kmac.finalize()
+}
+
+/// KMACXOF128 (NIST SP 800-185), the first OUTPUT_LEN bytes
+pub fn kmac_xof_128<const OUTPUT_LEN: usize>(
+    key: &[u8],
+    input: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut kmac = Kmac128::new(key, customization);
+    kmac.update(input);
+    let mut output = [0x00; OUTPUT_LEN];
+    kmac.finalize_xof().read(&mut output);
+    output
+}
+
+/// KMACXOF256 (NIST SP 800-185), the first OUTPUT_LEN bytes
+pub fn kmac_xof_256<const OUTPUT_LEN: usize>(
+    key: &[u8],
+    input: &[u8],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    let mut kmac = Kmac256::new(key, customization);
+    kmac.update(input);
+    let mut output = [0x00; OUTPUT_LEN];
+    kmac.finalize_xof().read(&mut output);
+    output
+}
+
+// Absorbs every input with its length, so the tuple ("ab", "c") and ("a", "bc") hash differently
+fn tuple_hash<const OUTPUT_LEN: usize>(
+    rate: usize,
+    inputs: &[&[u8]],
+    customization: &[u8],
+    length_encoding: u64,
+) -> [u8; OUTPUT_LEN] {
+    let mut sponge = cshake_sponge(rate, b"TupleHash", customization);
+    for input in inputs {
+        sponge.update(&encode_string(input));
+    }
+    sponge.update(&right_encode(length_encoding));
+    sponge.finalize::<OUTPUT_LEN>()
+}
+
+/// TupleHash128 (NIST SP 800-185), hashes a sequence of byte strings unambiguously
+pub fn tuple_hash_128<const OUTPUT_LEN: usize>(
+    inputs: &[&[u8]],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    tuple_hash(168, inputs, customization, OUTPUT_LEN as u64 * 8)
+}
+
+/// TupleHash256 (NIST SP 800-185), hashes a sequence of byte strings unambiguously
+pub fn tuple_hash_256<const OUTPUT_LEN: usize>(
+    inputs: &[&[u8]],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    tuple_hash(136, inputs, customization, OUTPUT_LEN as u64 * 8)
+}
+
+/// TupleHashXOF128 (NIST SP 800-185), the first OUTPUT_LEN bytes
+pub fn tuple_hash_xof_128<const OUTPUT_LEN: usize>(
+    inputs: &[&[u8]],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    tuple_hash(168, inputs, customization, 0)
+}
+
+/// TupleHashXOF256 (NIST SP 800-185), the first OUTPUT_LEN bytes
+pub fn tuple_hash_xof_256<const OUTPUT_LEN: usize>(
+    inputs: &[&[u8]],
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    tuple_hash(136, inputs, customization, 0)
+}
+
+// Hashes every block of the input on its own with SHAKE and then hashes the chaining values
+fn parallel_hash<const OUTPUT_LEN: usize>(
+    rate: usize,
+    input: &[u8],
+    block_size: usize,
+    customization: &[u8],
+    length_encoding: u64,
+) -> [u8; OUTPUT_LEN] {
+    assert_ne!(block_size, 0);
+    // The chaining values are twice the security level long
+    let chaining_value_len = 200 - rate;
+    let mut sponge = cshake_sponge(rate, b"ParallelHash", customization);
+    sponge.update(&left_encode(block_size as u64));
+    for block in input.chunks(block_size) {
+        let mut block_sponge = cshake_sponge(rate, b"", b"");
+        block_sponge.update(block);
+        let mut chaining_value = vec![0x00; chaining_value_len];
+        block_sponge.finalize_xof().read(&mut chaining_value);
+        sponge.update(&chaining_value);
+    }
+    sponge.update(&right_encode(input.len().div_ceil(block_size) as u64));
+    sponge.update(&right_encode(length_encoding));
+    sponge.finalize::<OUTPUT_LEN>()
+}
+
+/// ParallelHash128 (NIST SP 800-185), `block_size` is the size in bytes of the independently hashed blocks
+///
+/// ## Panics
+/// if `block_size == 0`
+pub fn parallel_hash_128<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    block_size: usize,
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    parallel_hash(168, input, block_size, customization, OUTPUT_LEN as u64 * 8)
+}
+
+/// ParallelHash256 (NIST SP 800-185), `block_size` is the size in bytes of the independently hashed blocks
+///
+/// ## Panics
+/// if `block_size == 0`
+pub fn parallel_hash_256<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    block_size: usize,
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    parallel_hash(136, input, block_size, customization, OUTPUT_LEN as u64 * 8)
+}
+
+/// ParallelHashXOF128 (NIST SP 800-185), the first OUTPUT_LEN bytes
+///
+/// ## Panics
+/// if `block_size == 0`
+pub fn parallel_hash_xof_128<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    block_size: usize,
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    parallel_hash(168, input, block_size, customization, 0)
+}
+
+/// ParallelHashXOF256 (NIST SP 800-185), the first OUTPUT_LEN bytes
+///
+/// ## Panics
+/// if `block_size == 0`
+pub fn parallel_hash_xof_256<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    block_size: usize,
+    customization: &[u8],
+) -> [u8; OUTPUT_LEN] {
+    parallel_hash(136, input, block_size, customization, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Integrity::SHA::SHA3::shake_128;
+    use const_hex::encode;
+
+    #[test]
+    fn encoding_test() {
+        assert_eq!(left_encode(0), [0x01, 0x00]);
+        assert_eq!(left_encode(168), [0x01, 168]);
+        assert_eq!(left_encode(0x1234), [0x02, 0x12, 0x34]);
+        assert_eq!(right_encode(0), [0x00, 0x01]);
+        assert_eq!(right_encode(256), [0x01, 0x00, 0x02]);
+        assert_eq!(encode_string(b""), [0x01, 0x00]);
+        assert_eq!(bytepad(b"ab", 4), [0x01, 0x04, b'a', b'b']);
+        assert_eq!(bytepad(b"abc", 4).len(), 8);
+    }
+
+    #[test]
+    fn cshake_test() {
+        // NIST samples
+        let data: Vec<u8> = (0x00..0xc8).collect();
+        assert_eq!(
+            encode(cshake_128::<32>(&data[..4], b"", b"Email Signature")),
+            "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5"
+        );
+        assert_eq!(
+            encode(cshake_128::<32>(&data, b"", b"Email Signature")),
+            "c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b"
+        );
+        assert_eq!(
+            encode(cshake_256::<64>(&data[..4], b"", b"Email Signature")),
+            "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd164020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c"
+        );
+        assert_eq!(
+            encode(cshake_256::<64>(&data, b"", b"Email Signature")),
+            "07dc27b11e51fbac75bc7b3c1d983e8b4b85fb1defaf218912ac86430273091727f42b17ed1df63e8ec118f04b23633c1dfb1574c8fb55cb45da8e25afb092bb"
+        );
+        // Without a name and customization it is SHAKE
+        assert_eq!(cshake_128::<50>(b"abc", b"", b""), shake_128::<50>(b"abc"));
+    }
+
+    #[test]
+    fn kmac_test() {
+        // NIST samples
+        let key: Vec<u8> = (0x40..0x60).collect();
+        let data: Vec<u8> = (0x00..0xc8).collect();
+        assert_eq!(
+            encode(kmac_128::<32>(&key, &data[..4], b"")),
+            "e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"
+        );
+        assert_eq!(
+            encode(kmac_128::<32>(&key, &data[..4], b"My Tagged Application")),
+            "3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5"
+        );
+        assert_eq!(
+            encode(kmac_128::<32>(&key, &data, b"My Tagged Application")),
+            "1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230"
+        );
+        assert_eq!(
+            encode(kmac_256::<64>(&key, &data[..4], b"My Tagged Application")),
+            "20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd"
+        );
+        assert_eq!(
+            encode(kmac_256::<64>(&key, &data, b"")),
+            "75358cf39e41494e949707927cee0af20a3ff553904c86b08f21cc414bcfd691589d27cf5e15369cbbff8b9a4c2eb17800855d0235ff635da82533ec6b759b69"
+        );
+        assert_eq!(
+            encode(kmac_xof_128::<32>(&key, &data[..4], b"")),
+            "cd83740bbd92ccc8cf032b1481a0f4460e7ca9dd12b08a0c4031178bacd6ec35"
+        );
+        assert_eq!(
+            encode(kmac_xof_256::<64>(&key, &data[..4], b"My Tagged Application")),
+            "1755133f1534752aad0748f2c706fb5c784512cab835cd15676b16c0c6647fa96faa7af634a0bf8ff6df39374fa00fad9a39e322a7c92065a64eb1fb0801eb2b"
+        );
+    }
+
+    #[test]
+    fn kmac_verify_test() {
+        let tag = kmac_256::<32>(b"key", b"message", b"app");
+        let mut kmac = Kmac256::new(b"key", b"app");
+        kmac.update(b"mess");
+        kmac.update(b"age");
+        assert_eq!(kmac.clone().verify::<32>(&tag), Ok(()));
+        let mut bad_tag = tag;
+        bad_tag[0] ^= 0x80;
+        assert_eq!(
+            kmac.clone().verify::<32>(&bad_tag),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+        // The tag can't pick its own length
+        let short_tag = kmac_256::<16>(b"key", b"message", b"app");
+        for tag in [&tag[..16], &short_tag, &[]] {
+            assert_eq!(
+                kmac.clone().verify::<32>(tag),
+                Err(AuthenticationError::TagNotMatchMessage)
+            );
+        }
+        assert_eq!(kmac.clone().verify::<16>(&short_tag), Ok(()));
+        // Nor can the verifier ask for less than the minimum
+        let tiny_tag = kmac_256::<1>(b"key", b"message", b"app");
+        assert_eq!(
+            kmac.clone().verify::<1>(&tiny_tag),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+        assert_eq!(
+            kmac.verify::<0>(&[]),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+    }
+
+    #[test]
+    fn tuple_hash_test() {
+        // NIST samples
+        let tuple: [&[u8]; 3] = [
+            &[0x00, 0x01, 0x02],
+            &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15],
+            &[0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28],
+        ];
+        assert_eq!(
+            encode(tuple_hash_128::<32>(&tuple[..2], b"")),
+            "c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1"
+        );
+        assert_eq!(
+            encode(tuple_hash_128::<32>(&tuple[..2], b"My Tuple App")),
+            "75cdb20ff4db1154e841d758e24160c54bae86eb8c13e7f5f40eb35588e96dfb"
+        );
+        assert_eq!(
+            encode(tuple_hash_128::<32>(&tuple, b"My Tuple App")),
+            "e60f202c89a2631eda8d4c588ca5fd07f39e5151998deccf973adb3804bb6e84"
+        );
+        assert_eq!(
+            encode(tuple_hash_256::<64>(&tuple[..2], b"")),
+            "cfb7058caca5e668f81a12a20a2195ce97a925f1dba3e7449a56f82201ec607311ac2696b1ab5ea2352df1423bde7bd4bb78c9aed1a853c78672f9eb23bbe194"
+        );
+        assert_eq!(
+            encode(tuple_hash_xof_128::<32>(&tuple[..2], b"")),
+            "2f103cd7c32320353495c68de1a8129245c6325f6f2a3d608d92179c96e68488"
+        );
+    }
+
+    #[test]
+    fn parallel_hash_test() {
+        // NIST samples
+        let data: Vec<u8> = [0x00, 0x10, 0x20]
+            .into_iter()
+            .flat_map(|high| (0..8).map(move |low| high + low))
+            .collect();
+        assert_eq!(
+            encode(parallel_hash_128::<32>(&data, 8, b"")),
+            "ba8dc1d1d979331d3f813603c67f72609ab5e44b94a0b8f9af46514454a2b4f5"
+        );
+        assert_eq!(
+            encode(parallel_hash_128::<32>(&data, 8, b"Parallel Data")),
+            "fc484dcb3f84dceedc353438151bee58157d6efed0445a81f165e495795b7206"
+        );
+        assert_eq!(
+            encode(parallel_hash_256::<64>(&data, 8, b"")),
+            "bc1ef124da34495e948ead207dd9842235da432d2bbc54b4c110e64c451105531b7f2a3e0ce055c02805e7c2de1fb746af97a1dd01f43b824e31b87612410429"
+        );
+        assert_eq!(
+            encode(parallel_hash_xof_128::<32>(&data, 8, b"")),
+            "fe47d661e49ffe5b7d999922c062356750caf552985b8e8ce6667f2727c3c8d3"
+        );
+    }
+}
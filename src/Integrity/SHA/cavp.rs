@@ -0,0 +1,215 @@
+//! Runs the NIST CAVP response files against the SHA family. The official byte-oriented files go
+//! in `test_vectors/cavp/byte` and the bit-oriented ones in `test_vectors/cavp/bit`, under the
+//! names they have in the NIST zips.
+use super::SHA1::*;
+use super::SHA2::*;
+use super::SHA3::*;
+use crate::test_vectors::{load_records, Record};
+use crate::Integrity::Sponge::Keccak::KeccakReader;
+use const_hex::encode;
+
+/// The directories of the byte-oriented and the bit-oriented files
+const ORIENTATIONS: [&str; 2] = ["byte", "bit"];
+
+/// The records of `test_vectors/cavp/<orientation>/<name>.rsp`
+fn load(orientation: &str, name: &str) -> Vec<Record> {
+    load_records(&format!("cavp/{orientation}/{name}.rsp"))
+}
+
+/// `Len`, `Msg` and `MD` records of the ShortMsg and LongMsg files
+fn run_msg(orientation: &str, name: &str, hash: &impl Fn(&[u8], u64) -> Vec<u8>) {
+    for record in load(orientation, name) {
+        let len = record.number("Len") as u64;
+        assert_eq!(
+            encode(hash(&record.hex("Msg"), len)),
+            record.field("MD"),
+            "{orientation}/{name} Len = {len}"
+        );
+    }
+}
+
+/// The SHA1 and SHA2 Monte Carlo test, every checkpoint hashes the last three digests 1000 times
+fn run_sha2_monte(name: &str, hash: &impl Fn(&[u8], u64) -> Vec<u8>) {
+    let records = load("byte", name);
+    let mut seed = records[0].hex("Seed");
+    for record in &records[1..] {
+        let mut digests = [seed.clone(), seed.clone(), seed];
+        for _ in 0..1000 {
+            let message = digests.concat();
+            let digest = hash(&message, 8 * message.len() as u64);
+            digests.rotate_left(1);
+            digests[2] = digest;
+        }
+        let [_, _, checkpoint] = digests;
+        assert_eq!(
+            encode(&checkpoint),
+            record.field("MD"),
+            "{name} COUNT = {}",
+            record.field("COUNT")
+        );
+        seed = checkpoint;
+    }
+}
+
+/// The SHA3 Monte Carlo test, every checkpoint hashes the last digest 1000 times
+fn run_sha3_monte(name: &str, hash: &impl Fn(&[u8], u64) -> Vec<u8>) {
+    let records = load("byte", name);
+    let mut digest = records[0].hex("Seed");
+    for record in &records[1..] {
+        for _ in 0..1000 {
+            digest = hash(&digest, 8 * digest.len() as u64);
+        }
+        assert_eq!(
+            encode(&digest),
+            record.field("MD"),
+            "{name} COUNT = {}",
+            record.field("COUNT")
+        );
+    }
+}
+
+/// ShortMsg and LongMsg of both orientations and Monte for a SHA1 or SHA2 hash. The Monte Carlo
+/// test only hashes whole digests, so it runs on the byte-oriented file.
+fn run_sha2(name: &str, hash: impl Fn(&[u8], u64) -> Vec<u8>) {
+    for orientation in ORIENTATIONS {
+        run_msg(orientation, &format!("{name}ShortMsg"), &hash);
+        run_msg(orientation, &format!("{name}LongMsg"), &hash);
+    }
+    run_sha2_monte(&format!("{name}Monte"), &hash);
+}
+
+/// ShortMsg and LongMsg of both orientations and Monte for a SHA3 hash
+fn run_sha3(name: &str, hash: impl Fn(&[u8], u64) -> Vec<u8>) {
+    for orientation in ORIENTATIONS {
+        run_msg(orientation, &format!("{name}ShortMsg"), &hash);
+        run_msg(orientation, &format!("{name}LongMsg"), &hash);
+    }
+    run_sha3_monte(&format!("{name}Monte"), &hash);
+}
+
+/// The first `output_bits` bits squeezed out of `reader`, a partial last byte keeps its bits in
+/// its low bits as the input does
+fn squeeze_bits(mut reader: KeccakReader, output_bits: usize) -> Vec<u8> {
+    let mut output = vec![0x00; output_bits.div_ceil(8)];
+    reader.read(&mut output);
+    if !output_bits.is_multiple_of(8) {
+        *output.last_mut().unwrap() &= (1 << (output_bits % 8)) - 1;
+    }
+    output
+}
+
+/// ShortMsg, LongMsg and VariableOut of both orientations and Monte for a SHAKE variant,
+/// `shake(message, bit_len)` gives the reader of the output
+fn run_shake(name: &str, shake: impl Fn(&[u8], u64) -> KeccakReader) {
+    for orientation in ORIENTATIONS {
+        for file in ["ShortMsg", "LongMsg"] {
+            for record in load(orientation, &format!("{name}{file}")) {
+                let len = record.number("Len");
+                assert_eq!(
+                    encode(squeeze_bits(
+                        shake(&record.hex("Msg"), len as u64),
+                        record.param("Outputlen")
+                    )),
+                    record.field("Output"),
+                    "{orientation}/{name}{file} Len = {len}"
+                );
+            }
+        }
+
+        for record in load(orientation, &format!("{name}VariableOut")) {
+            let input_len = record.param("Input Length") as u64;
+            assert_eq!(
+                encode(squeeze_bits(
+                    shake(&record.hex("Msg"), input_len),
+                    record.number("Outputlen")
+                )),
+                record.field("Output"),
+                "{orientation}/{name}VariableOut COUNT = {}",
+                record.field("COUNT")
+            );
+        }
+    }
+
+    // Every message is the first 16 bytes of the last output and its last two bytes pick the next output length
+    let records = load("byte", &format!("{name}Monte"));
+    let min_len = records[0].param("Minimum Output Length (bits)") / 8;
+    let max_len = records[0].param("Maximum Output Length (bits)") / 8;
+    let mut output = records[0].hex("Msg");
+    let mut output_len = max_len;
+    for record in &records[1..] {
+        for _ in 0..1000 {
+            let mut message = [0; 16];
+            let prefix_len = output.len().min(16);
+            message[..prefix_len].copy_from_slice(&output[..prefix_len]);
+            output = squeeze_bits(shake(&message, 128), 8 * output_len);
+            let last_bits =
+                u16::from_be_bytes([output[output.len() - 2], output[output.len() - 1]]);
+            output_len = min_len + last_bits as usize % (max_len - min_len + 1);
+        }
+        assert_eq!(
+            8 * output.len(),
+            record.number("Outputlen"),
+            "{name}Monte COUNT = {}",
+            record.field("COUNT")
+        );
+        assert_eq!(
+            encode(&output),
+            record.field("Output"),
+            "{name}Monte COUNT = {}",
+            record.field("COUNT")
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs the NIST CAVP files in test_vectors/cavp"]
+fn cavp_sha1_test() {
+    run_sha2("SHA1", |input, bit_len| sha1_bits(input, bit_len).to_vec());
+}
+
+#[test]
+#[ignore = "needs the NIST CAVP files in test_vectors/cavp"]
+fn cavp_sha2_test() {
+    run_sha2("SHA224", |input, bit_len| {
+        sha224_bits(input, bit_len).to_vec()
+    });
+    run_sha2("SHA256", |input, bit_len| {
+        sha256_bits(input, bit_len).to_vec()
+    });
+    run_sha2("SHA384", |input, bit_len| {
+        sha384_bits(input, bit_len).to_vec()
+    });
+    run_sha2("SHA512", |input, bit_len| {
+        sha512_bits(input, bit_len).to_vec()
+    });
+    run_sha2("SHA512_224", |input, bit_len| {
+        sha512_to_224_bits(input, bit_len).to_vec()
+    });
+    run_sha2("SHA512_256", |input, bit_len| {
+        sha512_to_256_bits(input, bit_len).to_vec()
+    });
+}
+
+#[test]
+#[ignore = "needs the NIST CAVP files in test_vectors/cavp"]
+fn cavp_sha3_test() {
+    run_sha3("SHA3_224", |input, bit_len| {
+        sha_3_224_bits(input, bit_len).to_vec()
+    });
+    run_sha3("SHA3_256", |input, bit_len| {
+        sha_3_256_bits(input, bit_len).to_vec()
+    });
+    run_sha3("SHA3_384", |input, bit_len| {
+        sha_3_384_bits(input, bit_len).to_vec()
+    });
+    run_sha3("SHA3_512", |input, bit_len| {
+        sha_3_512_bits(input, bit_len).to_vec()
+    });
+}
+
+#[test]
+#[ignore = "needs the NIST CAVP files in test_vectors/cavp"]
+fn cavp_shake_test() {
+    run_shake("SHAKE128", shake_128_bits_xof);
+    run_shake("SHAKE256", shake_256_bits_xof);
+}
@@ -1,6 +1,8 @@
 use super::SHA3::*;
 use super::SHA2::*;
 use super::SHA1::*;
+use super::Midstate;
+use crate::Integrity::Digest;
 use const_hex::encode;
 use std::env;
 
@@ -106,6 +108,131 @@ fn sha_512_to_t_merkle_test() {
     );
 }
 
+#[test]
+fn sha_512_t_test() {
+    assert_eq!(sha512_t(b"abc", 224), sha512_to_224(b"abc"));
+    assert_eq!(sha512_t(b"abc", 256), sha512_to_256(b"abc"));
+    assert_eq!(encode(sha512_t(b"abc", 8)), "c5");
+    assert_eq!(encode(sha512_t(b"abc", 100)), "36cc539a771da9ad5726499d80");
+    assert_eq!(
+        encode(sha512_t(b"abc", 252)),
+        "e549922e5e47cf602806d2e232230db102b266d503df2121fc8e93ecd347e430"
+    );
+    assert_eq!(
+        encode(sha512_t(b"abc", 511)),
+        "71a80c6a46fbd2d092522f3a5d7750b9daa2c59f2ff05dfde25cd68e53317f4e79a080da3d4145b3fc2d8fe520cd787da4bb0165a90296a99a9a9b87994a087c"
+    );
+    assert_eq!(
+        encode(sha512_t(b"", 200)),
+        "241d34eb0be2fbdc0ccfbe2c6973bffaa541b37845c678ea89"
+    );
+    let mut hasher = Sha512T::new(100);
+    hasher.update(b"a");
+    hasher.update(b"bc");
+    assert_eq!(hasher.finalize(), sha512_t(b"abc", 100));
+}
+
+#[test]
+#[should_panic]
+fn sha_512_t_384_test() {
+    let _ = sha512_t(b"abc", 384);
+}
+
+#[test]
+fn sha_bits_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 37 + 11) as u8).collect();
+    let expected = [
+        (0, "da39a3ee5e6b4b0d3255bfef95601890afd80709", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        (1, "bb6b3e18f0115b57925241676f5b1ae88747b08a", "bd4f9e98beb68c6ead3243b1b4c7fed75fa4feaab1f84795cbd8a98676a2a375"),
+        (5, "102feb957cec1703035e172d4bf4b5cec8ffbe30", "18a911498e1f684d15c187d035e0ab18e46f373cbe25469ddb808a33cef2c00b"),
+        (447, "9375a3f4a9e8174bd59d30b3e5dfd5e1275718fc", "debb15d7c8a946e6ab159390ebec6b1a166ebd214c0516fcb13506d66ec7adad"),
+        (449, "a1ba3c9651204df3ac44624833749231c5adc33c", "7f0928a907ef6c4f47abe8d6970ecb2efaa5f75cc03147a04e9eb0c3c71ec579"),
+        (511, "012d06ac1fdef4cb82b5e02183ca855ee358b5f0", "41c9cd8823c1fe7dda3fb8ea9430552b77a7161cd8bb9d8a6d5890af3edeac41"),
+        (1791, "f4739c1e69345975cdb7cb0b65eaec4fa479ed9e", "dd9cd3613f578aadded10a68365e7b7834cd0ae365815b3c751abe82b9d6891f"),
+    ];
+    for (bit_len, sha1_hash, sha256_hash) in expected {
+        assert_eq!(encode(sha1_bits(&message, bit_len)), sha1_hash);
+        assert_eq!(encode(sha256_bits(&message, bit_len)), sha256_hash);
+    }
+    assert_eq!(
+        encode(sha512_bits(&message, 895)),
+        "f68fe1f169575909d7edecdafbb1400faa27a394fe06d59552e5137318955dad340a209c2f863c7ccc050a158b8b68ab507d66f50edd09f18a4d5b77c08ad307"
+    );
+    assert_eq!(
+        encode(sha512_bits(&message, 1023)),
+        "9765594ebfec45973d0cf8413da8551a84c6db3630814d7e2d8f4b0e924984d8449fa4e91165b83bd5d2e969c48f6beb03cfa473f878142c386a7c58385c25e1"
+    );
+    // Whole bytes give the same digest as the byte functions
+    assert_eq!(sha224_bits(&message, 8 * 100), sha224(&message[..100]));
+    assert_eq!(sha384_bits(&message, 8 * 200), sha384(&message[..200]));
+    assert_eq!(sha512_bits(&message, 8 * 112), sha512(&message[..112]));
+}
+
+#[test]
+fn sha512_t_bits_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 37 + 11) as u8).collect();
+    assert_eq!(
+        encode(sha512_to_224_bits(&message, 5)),
+        "1f555ceed3f5d4018fa43d4f0078ef69c2044be29c00e1b174f01c5b"
+    );
+    assert_eq!(
+        encode(sha512_to_224_bits(&message, 1023)),
+        "d182b0371eda7ae242cc4569919f5b6f889caed175e6af9e4f14d5e3"
+    );
+    assert_eq!(
+        encode(sha512_to_256_bits(&message, 449)),
+        "ff01d49cfe33fc7994dae13dc95eede9711c2907f1b98056311f392eff3acf44"
+    );
+    assert_eq!(
+        encode(sha512_to_256_bits(&message, 0)),
+        "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a"
+    );
+    assert_eq!(encode(sha512_t_bits(&message, 7, 100)), "eb4aecdc696110a43606dd9790");
+    assert_eq!(
+        encode(sha512_t_bits(&message, 895, 511)),
+        "fa85480d23c600efae9a9164b4e4a33e9014924af0cba639d115268cf3ebb6dd5bd5ad3d30b00b1e90821e37182779fd2e516a95b5ac50a3c7ff79209595600c"
+    );
+    // Whole bytes give the same digest as the byte functions
+    assert_eq!(sha512_to_224_bits(&message, 8 * 111), sha512_to_224(&message[..111]));
+    assert_eq!(sha512_t_bits(&message, 8 * 130, 200), sha512_t(&message[..130], 200));
+}
+
+#[test]
+fn sha3_bits_test() {
+    // NIST examples
+    assert_eq!(
+        encode(sha_3_224_bits(&[0x13], 5)),
+        "ffbad5da96bad71789330206dc6768ecaeb1b32dca6b3301489674ab"
+    );
+    assert_eq!(
+        encode(sha_3_256_bits(&[0x13], 5)),
+        "7b0047cf5a456882363cbf0fb05322cf65f4b7059a46365e830132e3b5d957af"
+    );
+    assert_eq!(
+        encode(sha_3_224_bits(&[0x53, 0x58, 0x7b, 0x19], 30)),
+        "d666a514cc9dba25ac1ba69ed3930460deaac9851b5f0baab007df3b"
+    );
+
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 37 + 11) as u8).collect();
+    let expected = [
+        (1, "83f66216d2cc769e153bafce0181b61a471b4c6a213fc6f59a42985f976f33fe", "360054be974a5fbdbb5fb9ca76c6569a0c08b7181c22b34d51338797d38eca8af6fbcfcb014ce67a"),
+        (7, "9e5f8c800689fa5168fc5fbfeca8bd5b3668ffd6f08143e2e396b9ae0f9b443e", "d7d4bf80d239b9034006f6a9ea105714f77e73d2920215e25323ca7d9f0c424b4f56acef6fafe8bc"),
+        (1081, "e1aa1d728409ba592b5f778f366b2d3172b3b9005adad79b428d1e5a049be039", "c49403486f404d84b5dae6a64773190167142e1eeb5c6e691d9efd7c2bdb072f012b70f1eeb707e4"),
+        (1087, "258ffff6a08050336fc7e439073280108fde67755b1fbe16893a7f9e2998d4db", "87149c26d623bde5a38aa37878762cbb8d18b806c2e28b84290bb82a9253129ff9b41250f92b6f0d"),
+        (1343, "f8fa07927557143764b7a24d81b01247eefb5990ab400413eda7be0515ac1b58", "5e39155dace7c1f11172ce42ef9f1e088702d1a91df39e594288c3b5e8cb93dc45fe38b0c8439b12"),
+    ];
+    for (bit_len, sha3_hash, shake_hash) in expected {
+        assert_eq!(encode(sha_3_256_bits(&message, bit_len)), sha3_hash);
+        assert_eq!(encode(shake_128_bits::<40>(&message, bit_len)), shake_hash);
+    }
+    assert_eq!(sha_3_256_bits(&message, 1088), sha_3_256(&message[..136]));
+    assert_eq!(
+        shake_128_bits::<40>(&message, 1344),
+        shake_128::<40>(&message[..168])
+    );
+    assert_eq!(sha_3_512_bits(&message, 8 * 71), sha_3_512(&message[..71]));
+}
+
 #[test]
 fn sha_3_224_test() {
     let hello_hash_224 = encode(sha_3_244(b"Hello"));
@@ -141,3 +268,305 @@ fn sha_3_512_test() {
         "0b8a44ac991e2b263e8623cfbeefc1cffe8c1c0de57b3e2bf1673b4f35e660e89abd18afb7ac93cf215eba36dd1af67698d6c9ca3fdaaf734ffc4bd5a8e34627"
     )
 }
+
+#[test]
+fn sha_two_block_padding_test() {
+    // 56 and 112 bytes do not leave room for the length in the last block
+    let message56 = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    assert_eq!(
+        encode(sha1(message56)),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+    assert_eq!(
+        encode(sha256(message56)),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    let message112 = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+    assert_eq!(
+        encode(sha512(message112)),
+        "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"
+    );
+}
+
+#[test]
+fn sha_incremental_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+    for len in [0, 1, 55, 56, 63, 64, 65, 111, 112, 127, 128, 129, 300] {
+        let input = &message[..len];
+        for piece_size in [1, 3, 64, 100, 128] {
+            let mut sha1_hasher = Sha1::new();
+            let mut sha224_hasher = Sha224::new();
+            let mut sha256_hasher = Sha256::new();
+            let mut sha384_hasher = Sha384::new();
+            let mut sha512_hasher = Sha512::new();
+            let mut sha512_224_hasher = Sha512_224::new();
+            let mut sha512_256_hasher = Sha512_256::new();
+            for piece in input.chunks(piece_size) {
+                sha1_hasher.update(piece);
+                sha224_hasher.update(piece);
+                sha256_hasher.update(piece);
+                sha384_hasher.update(piece);
+                sha512_hasher.update(piece);
+                sha512_224_hasher.update(piece);
+                sha512_256_hasher.update(piece);
+            }
+            assert_eq!(sha1_hasher.finalize(), sha1(input));
+            assert_eq!(sha224_hasher.finalize(), sha224(input));
+            assert_eq!(sha256_hasher.finalize(), sha256(input));
+            assert_eq!(sha384_hasher.finalize(), sha384(input));
+            assert_eq!(sha512_hasher.finalize(), sha512(input));
+            assert_eq!(sha512_224_hasher.finalize(), sha512_to_224(input));
+            assert_eq!(sha512_256_hasher.finalize(), sha512_to_256(input));
+        }
+    }
+}
+
+#[test]
+fn sha_batch_test() {
+    // Enough bytes in total to be split over threads
+    let messages: Vec<Vec<u8>> = (0..400u32)
+        .map(|i| (0..i).map(|x| (x * 7 + i) as u8).collect())
+        .collect();
+    let inputs: Vec<&[u8]> = messages.iter().map(|message| message.as_slice()).collect();
+    for inputs in [&inputs[..3], &inputs[..]] {
+        let mut sha1_outputs = vec![[0x00; 20]; inputs.len()];
+        let mut sha256_outputs = vec![[0x00; 32]; inputs.len()];
+        let mut sha512_outputs = vec![[0x00; 64]; inputs.len()];
+        sha1_batch(inputs, &mut sha1_outputs);
+        sha256_batch(inputs, &mut sha256_outputs);
+        sha512_batch(inputs, &mut sha512_outputs);
+        for (i, input) in inputs.iter().enumerate() {
+            assert_eq!(sha1_outputs[i], sha1(input));
+            assert_eq!(sha256_outputs[i], sha256(input));
+            assert_eq!(sha512_outputs[i], sha512(input));
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn sha_batch_length_mismatch_test() {
+    sha256_batch(&[b"a", b"b"], &mut [[0x00; 32]]);
+}
+
+#[test]
+fn midstate_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+    let mut sha256_hasher = Sha256::new();
+    sha256_hasher.update(&message[..128]);
+    let midstate = sha256_hasher.midstate().unwrap();
+    assert_eq!(midstate.byte_count, 128);
+    let mut resumed = Sha256::from_midstate(midstate);
+    resumed.update(&message[128..]);
+    assert_eq!(resumed.finalize(), sha256(&message));
+
+    let mut sha1_hasher = Sha1::new();
+    sha1_hasher.update(&message[..64]);
+    let mut resumed = Sha1::from_midstate(sha1_hasher.midstate().unwrap());
+    resumed.update(&message[64..]);
+    assert_eq!(resumed.finalize(), sha1(&message));
+
+    let mut sha512_hasher = Sha512::new();
+    sha512_hasher.update(&message[..256]);
+    let mut resumed = Sha512::from_midstate(sha512_hasher.midstate().unwrap());
+    resumed.update(&message[256..]);
+    assert_eq!(resumed.finalize(), sha512(&message));
+
+    // No midstate in the middle of a block
+    sha512_hasher.update(b"a");
+    assert_eq!(sha512_hasher.midstate(), None);
+}
+
+#[test]
+fn length_extension_test() {
+    // Knowing only the digest and the length of secret || message, a suffix can be appended
+    let secret_and_message = b"secret key||amount=10";
+    let digest = sha256(secret_and_message);
+    let (glue, glue_blocks) =
+        super::sha_pad_last_chunk::<64>(secret_and_message, secret_and_message.len() as u128);
+    let glue = &glue.concat()[secret_and_message.len()..64 * glue_blocks];
+
+    let mut state = [0u32; 8];
+    for (word, bytes) in state.iter_mut().zip(digest.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    let mut forger = Sha256::from_midstate(Midstate {
+        state,
+        byte_count: 64,
+    });
+    forger.update(b"&amount=1000");
+
+    let forged_message = [&secret_and_message[..], glue, b"&amount=1000"].concat();
+    assert_eq!(forger.finalize(), sha256(&forged_message));
+}
+
+#[test]
+fn midstate_long_message_test() {
+    // SHA-512 has a 128 bit length field, so 2^61 bytes are fine
+    let mut hasher = Sha512::from_midstate(Midstate {
+        state: [0u64; 8],
+        byte_count: 1 << 61,
+    });
+    hasher.update(b"abc");
+    hasher.finalize();
+    let (padding, blocks) = super::sha_pad_last_chunk::<128>(&[], 1 << 61);
+    assert_eq!(blocks, 1);
+    assert_eq!(padding[0][0], 0x80);
+    assert_eq!(padding[0][112..], (1u128 << 64).to_be_bytes());
+}
+
+#[test]
+#[should_panic(expected = "fewer bytes than the padding can encode")]
+fn midstate_too_long_test() {
+    // 2^64 bits don't fit in the 64 bit length field of SHA-256
+    let _ = Sha256::from_midstate(Midstate {
+        state: [0u32; 8],
+        byte_count: 1 << 61,
+    });
+}
+
+#[test]
+#[should_panic]
+fn midstate_partial_block_test() {
+    let _ = Sha256::from_midstate(Midstate {
+        state: [0u32; 8],
+        byte_count: 10,
+    });
+}
+
+#[test]
+fn shake_test() {
+    assert_eq!(
+        encode(shake_128::<32>(b"")),
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+    );
+    assert_eq!(
+        encode(shake_256::<64>(b"")),
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+    );
+    // Padding needs only one or two bytes here
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+    assert_eq!(
+        encode(sha_3_256(&message[..134])),
+        "c8883239d2ce058b11d87de7cd74805cb7e073b1b4884636fb2b5d1982c7bb9d"
+    );
+    assert_eq!(
+        encode(shake_256::<32>(&message[..135])),
+        "0213fc98352f009fafdf8ee1ea36391485a85aa6f6c07a5cd81266d21eb17f9a"
+    );
+}
+
+#[test]
+fn shake_xof_reader_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+    let expected = "f34bb9262026db5355e8cdf4cdf1444bf541c23220c25f6834b4615a988c6753cb2971dc21afbbebd240017de99a26af324939835e1ff514301dab5227b82e88ea7c6d8bae17d3fc5d8be27dddc396855854436c615e9564387ae0fd811d2d788047678021c987052d21df90766d021f01b0338c36fd7d31f007a9af7512ecaad542d27ef9c1f35528957f49a967a3f849b5f1150a6440e07670422b9c0afd09801f4f6c731144e567baa820e471cd85b7e36958f6b3fc2f0ed8d8ea145dccad23028b59570ddc4f";
+    let mut hasher = Shake128::new();
+    for piece in message[..166].chunks(17) {
+        hasher.update(piece);
+    }
+    let mut reader = hasher.finalize_xof();
+    // Read it in uneven pieces that cross the rate boundary
+    let mut output = [0x00; 200];
+    let (first, rest) = output.split_at_mut(7);
+    reader.read(first);
+    let (second, third) = rest.split_at_mut(165);
+    reader.read(second);
+    reader.read(third);
+    assert_eq!(encode(output), expected);
+    assert_eq!(shake_128::<200>(&message[..166]), output);
+}
+
+#[test]
+fn sha3_incremental_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+    for len in [0, 1, 71, 72, 73, 103, 104, 135, 136, 137, 143, 144, 300] {
+        let input = &message[..len];
+        for piece_size in [1, 5, 72, 136, 200] {
+            let mut sha3_224_hasher = Sha3_224::new();
+            let mut sha3_256_hasher = Sha3_256::new();
+            let mut sha3_384_hasher = Sha3_384::new();
+            let mut sha3_512_hasher = Sha3_512::new();
+            let mut shake_256_hasher = Shake256::new();
+            for piece in input.chunks(piece_size) {
+                sha3_224_hasher.update(piece);
+                sha3_256_hasher.update(piece);
+                sha3_384_hasher.update(piece);
+                sha3_512_hasher.update(piece);
+                shake_256_hasher.update(piece);
+            }
+            assert_eq!(sha3_224_hasher.finalize(), sha_3_244(input));
+            assert_eq!(sha3_256_hasher.finalize(), sha_3_256(input));
+            assert_eq!(sha3_384_hasher.finalize(), sha_3_384(input));
+            assert_eq!(sha3_512_hasher.finalize(), sha_3_512(input));
+            let mut shake_output = [0x00; 300];
+            shake_256_hasher.finalize_xof().read(&mut shake_output);
+            assert_eq!(shake_output, shake_256::<300>(input));
+        }
+    }
+}
+
+#[test]
+fn keccak_legacy_test() {
+    assert_eq!(
+        encode(keccak_224(b"")),
+        "f71837502ba8e10837bdd8d365adb85591895602fc552b48b7390abd"
+    );
+    assert_eq!(
+        encode(keccak_256(b"")),
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(
+        encode(keccak_256(b"abc")),
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+    );
+    assert_eq!(
+        encode(keccak_384(b"")),
+        "2c23146a63a29acf99e73b88f8c24eaa7dc60aa771780ccc006afbfa8fe2479b2dd2b21362337441ac12b515911957ff"
+    );
+    assert_eq!(
+        encode(keccak_512(b"abc")),
+        "18587dc2ea106b9a1563e32b3312421ca164c7f1f07bc922a9c83d77cea3a1e5d0c69910739025372dc14ac9642629379540c17e2a65b19d77aa511a9d00bb96"
+    );
+    // Only the padding differs from SHA3
+    assert_ne!(keccak_256(b"abc"), sha_3_256(b"abc"));
+}
+
+// Hashes the message in pieces through nothing but the trait
+fn digest_in_pieces<D: Digest>(input: &[u8]) -> D::Output {
+    let mut hasher = D::new();
+    for piece in input.chunks(D::BLOCK_SIZE - 1) {
+        hasher.update(piece);
+    }
+    hasher.finalize()
+}
+
+#[test]
+fn digest_trait_test() {
+    let message: Vec<u8> = (0..300u32).map(|x| (x * 7 + 3) as u8).collect();
+    assert_eq!(digest_in_pieces::<Sha1>(&message), sha1(&message));
+    assert_eq!(digest_in_pieces::<Sha224>(&message), sha224(&message));
+    assert_eq!(digest_in_pieces::<Sha256>(&message), sha256(&message));
+    assert_eq!(digest_in_pieces::<Sha384>(&message), sha384(&message));
+    assert_eq!(digest_in_pieces::<Sha512>(&message), sha512(&message));
+    assert_eq!(
+        digest_in_pieces::<Sha512_224>(&message),
+        sha512_to_224(&message)
+    );
+    assert_eq!(
+        digest_in_pieces::<Sha512_256>(&message),
+        sha512_to_256(&message)
+    );
+    assert_eq!(digest_in_pieces::<Sha3_224>(&message), sha_3_244(&message));
+    assert_eq!(digest_in_pieces::<Sha3_256>(&message), sha_3_256(&message));
+    assert_eq!(digest_in_pieces::<Sha3_384>(&message), sha_3_384(&message));
+    assert_eq!(digest_in_pieces::<Sha3_512>(&message), sha_3_512(&message));
+    assert_eq!(digest_in_pieces::<Keccak224>(&message), keccak_224(&message));
+    assert_eq!(digest_in_pieces::<Keccak256>(&message), keccak_256(&message));
+    assert_eq!(digest_in_pieces::<Keccak384>(&message), keccak_384(&message));
+    assert_eq!(digest_in_pieces::<Keccak512>(&message), keccak_512(&message));
+
+    assert_eq!(Sha256::OUTPUT_SIZE, 32);
+    assert_eq!(Sha512::BLOCK_SIZE, 128);
+    assert_eq!(Sha3_256::BLOCK_SIZE, 136);
+    assert_eq!(<Sha256 as Digest>::digest(b"lol"), sha256(b"lol"));
+}
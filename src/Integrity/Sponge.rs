@@ -1,7 +1,10 @@
 use std::fmt::Debug;
 
 pub mod Keccak;
-mod Photon;
+pub mod Photon;
+pub mod PhotonBeetle;
+#[cfg(test)]
+mod kat;
 
 pub(crate) fn zip_with<const N: usize, T, U, V: Debug, F: Fn(T, U) -> V>(
     arr1: [T; N],
@@ -81,6 +84,133 @@ where
     )
 }
 
+/// The state a sponge permutes. Input blocks are xored into its first bytes and the output is
+/// read out of them, so a permutation can keep the state in whatever form it works on.
+pub trait SpongeState: Clone {
+    /// The size of the state in bytes
+    const SIZE: usize;
+    /// The all zero state
+    const ZERO: Self;
+
+    /// Xors `block` into the first `block.len()` bytes of the state
+    fn xor_bytes(&mut self, block: &[u8]);
+
+    /// The byte at `index` of the state
+    fn byte(&self, index: usize) -> u8;
+}
+
+impl<const STATE_SIZE: usize> SpongeState for [u8; STATE_SIZE] {
+    const SIZE: usize = STATE_SIZE;
+    const ZERO: Self = [0x00; STATE_SIZE];
+
+    fn xor_bytes(&mut self, block: &[u8]) {
+        for (s, b) in self.iter_mut().zip(block) {
+            *s ^= b;
+        }
+    }
+
+    fn byte(&self, index: usize) -> u8 {
+        self[index]
+    }
+}
+
+/// Incremental version of `extended_sponge`. Absorbs input as it comes and buffers the
+/// partial block, so the padding is only applied when finalizing.
+#[derive(Clone)]
+pub(crate) struct IncrementalSponge<S> {
+    perm_fun: fn(&mut S),
+    absorb_rate: usize,
+    squeeze_rate: usize,
+    state: S,
+    buffer: Vec<u8>,
+}
+
+impl<S: SpongeState> IncrementalSponge<S> {
+    pub(crate) fn new(
+        perm_fun: fn(&mut S),
+        absorb_rate: usize,
+        squeeze_rate: usize,
+        initialization_state: S,
+    ) -> Self {
+        assert!(absorb_rate <= S::SIZE && squeeze_rate <= S::SIZE);
+        IncrementalSponge {
+            perm_fun,
+            absorb_rate,
+            squeeze_rate,
+            state: initialization_state,
+            buffer: Vec::with_capacity(absorb_rate),
+        }
+    }
+
+    fn absorb_block(state: &mut S, perm_fun: fn(&mut S), block: &[u8]) {
+        state.xor_bytes(block);
+        perm_fun(state);
+    }
+
+    pub(crate) fn absorb(&mut self, input: &[u8]) {
+        let mut input = input;
+
+        // Fill up a partially filled buffer first
+        if !self.buffer.is_empty() {
+            let needed = (self.absorb_rate - self.buffer.len()).min(input.len());
+            self.buffer.extend_from_slice(&input[..needed]);
+            input = &input[needed..];
+            if self.buffer.len() < self.absorb_rate {
+                return;
+            }
+            Self::absorb_block(&mut self.state, self.perm_fun, &self.buffer);
+            self.buffer.clear();
+        }
+
+        // Absorb whole blocks straight from the input
+        let mut blocks = input.chunks_exact(self.absorb_rate);
+        for block in blocks.by_ref() {
+            Self::absorb_block(&mut self.state, self.perm_fun, block);
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    /// Pads the buffered input with `pad_fun` and absorbs it. The returned reader squeezes the output.
+    pub(crate) fn finalize<PD>(mut self, pad_fun: PD) -> SpongeReader<S>
+    where
+        PD: Fn(&[u8], usize) -> Vec<u8>,
+    {
+        let padded_message: Vec<u8> = pad_fun(&self.buffer, self.absorb_rate);
+        assert_eq!(padded_message.len() % self.absorb_rate, 0);
+        for P in padded_message.chunks(self.absorb_rate) {
+            Self::absorb_block(&mut self.state, self.perm_fun, P);
+        }
+        SpongeReader {
+            perm_fun: self.perm_fun,
+            squeeze_rate: self.squeeze_rate,
+            state: self.state,
+            position: 0,
+        }
+    }
+}
+
+/// The squeezing phase of a sponge. Can be read from any number of times.
+#[derive(Clone)]
+pub(crate) struct SpongeReader<S> {
+    perm_fun: fn(&mut S),
+    squeeze_rate: usize,
+    state: S,
+    position: usize,
+}
+
+impl<S: SpongeState> SpongeReader<S> {
+    pub(crate) fn read(&mut self, output: &mut [u8]) {
+        for byte in output.iter_mut() {
+            if self.position == self.squeeze_rate {
+                (self.perm_fun)(&mut self.state);
+                self.position = 0;
+            }
+            *byte = self.state.byte(self.position);
+            self.position += 1;
+        }
+    }
+}
+
 struct Duplex<F1, F2, const STATE_SIZE: usize>
 where
     F1: Fn(&mut [u8; STATE_SIZE]),
@@ -1,6 +1,4 @@
-use super::sponge;
-use ndarray::prelude::*;
-use std::ops::{Index, IndexMut};
+use super::{IncrementalSponge, SpongeReader, SpongeState};
 
 /// The Keccak sponge hashing function used in the SHA3 family of hashing functions. This implementation used the padding used in SHA3
 pub fn keccak<const OUTPUT_LEN: usize>(
@@ -8,9 +6,135 @@ pub fn keccak<const OUTPUT_LEN: usize>(
     rate: usize,
     delimited_suffix: u8,
 ) -> [u8; OUTPUT_LEN] {
-    let pad_fun =
-        |pad_input: &[u8], pad_rate: usize| keccak_padding(pad_input, pad_rate, delimited_suffix);
-    sponge::<_, _, OUTPUT_LEN, 200>(keccak_f_1600, pad_fun, rate, input)
+    let mut keccak = Keccak::new(rate, delimited_suffix);
+    keccak.update(input);
+    keccak.finalize()
+}
+
+/// The Keccak sponge on the first `bit_len` bits of `input`. As in FIPS 202 the bits of every byte
+/// are taken least significant first, so the last partial byte holds its bits in its low bits.
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn keccak_bits<const OUTPUT_LEN: usize>(
+    input: &[u8],
+    bit_len: u64,
+    rate: usize,
+    delimited_suffix: u8,
+) -> [u8; OUTPUT_LEN] {
+    let mut output = [0x00; OUTPUT_LEN];
+    keccak_bits_xof(input, bit_len, rate, delimited_suffix).read(&mut output);
+    output
+}
+
+/// `keccak_bits` with an arbitrary amount of output
+///
+/// ## Panics
+/// if `bit_len > 8 * input.len()`
+pub fn keccak_bits_xof(
+    input: &[u8],
+    bit_len: u64,
+    rate: usize,
+    delimited_suffix: u8,
+) -> KeccakReader {
+    assert!(
+        bit_len <= 8 * input.len() as u64,
+        "The input is shorter than bit_len"
+    );
+    let padded = keccak_bit_padding(input, bit_len, rate, delimited_suffix);
+    let mut sponge = IncrementalSponge::new(keccak_f_1600, rate, rate, [0u64; 25]);
+    sponge.absorb(&padded);
+    KeccakReader {
+        reader: sponge.finalize(|padded: &[u8], _| padded.to_vec()),
+    }
+}
+
+/// Incremental Keccak sponge. Absorb the input in pieces with `update` and then either take a
+/// fixed length digest with `finalize` or an arbitrary amount of output with `finalize_xof`.
+/// The state defaults to the 1600 bit state of SHA3 kept as its 25 lanes, `with_permutation`
+/// takes a permutation of any other width.
+#[derive(Clone)]
+pub struct Keccak<S = [u64; 25]> {
+    sponge: IncrementalSponge<S>,
+    delimited_suffix: u8,
+}
+
+impl Keccak {
+    /// ## Panics
+    /// if `rate == 0` or `rate > 200`
+    pub fn new(rate: usize, delimited_suffix: u8) -> Keccak {
+        Keccak::with_permutation(keccak_f_1600, rate, delimited_suffix)
+    }
+}
+
+impl<S: SpongeState> Keccak<S> {
+    /// A Keccak sponge over another permutation, for example a reduced round
+    /// `keccak_p_1600::<12>` or `keccak_f_200` on the 200 bit state
+    ///
+    /// ## Panics
+    /// if `rate == 0` or `rate` is larger than the state
+    pub fn with_permutation(
+        permutation: fn(&mut S),
+        rate: usize,
+        delimited_suffix: u8,
+    ) -> Keccak<S> {
+        assert!(0 < rate && rate <= S::SIZE);
+        Keccak {
+            sponge: IncrementalSponge::new(permutation, rate, rate, S::ZERO),
+            delimited_suffix,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.sponge.absorb(input)
+    }
+
+    pub fn finalize<const OUTPUT_LEN: usize>(self) -> [u8; OUTPUT_LEN] {
+        let mut output = [0x00; OUTPUT_LEN];
+        self.finalize_xof().read(&mut output);
+        output
+    }
+
+    pub fn finalize_xof(self) -> KeccakReader<S> {
+        let delimited_suffix = self.delimited_suffix;
+        KeccakReader {
+            reader: self.sponge.finalize(|pad_input: &[u8], pad_rate: usize| {
+                keccak_padding(pad_input, pad_rate, delimited_suffix)
+            }),
+        }
+    }
+}
+
+/// Squeezes output out of a finalized Keccak sponge. Consecutive reads continue where the last one ended.
+#[derive(Clone)]
+pub struct KeccakReader<S = [u64; 25]> {
+    reader: SpongeReader<S>,
+}
+
+impl<S: SpongeState> KeccakReader<S> {
+    pub fn read(&mut self, output: &mut [u8]) {
+        self.reader.read(output)
+    }
+}
+
+/// The 1600 bit state as the 25 lanes `keccak_f_1600` works on, every lane in little endian
+impl SpongeState for [u64; 25] {
+    const SIZE: usize = 200;
+    const ZERO: Self = [0; 25];
+
+    fn xor_bytes(&mut self, block: &[u8]) {
+        let mut lane_bytes = block.chunks_exact(8);
+        for (lane, bytes) in self.iter_mut().zip(lane_bytes.by_ref()) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        for (i, byte) in lane_bytes.remainder().iter().enumerate() {
+            self[block.len() / 8] ^= u64::from(*byte) << (8 * i);
+        }
+    }
+
+    fn byte(&self, index: usize) -> u8 {
+        (self[index / 8] >> (8 * (index % 8))) as u8
+    }
 }
 
 const ROUND_CONSTANTS: [u64; 24] = [
@@ -40,79 +164,240 @@ const ROUND_CONSTANTS: [u64; 24] = [
     0x8000000080008008,
 ];
 
-fn keccak_f_1600(state: &mut [u8; 200]) {
-    let mut array: Array2<u64> = Array2::from_shape_vec(
-        (5, 5).f(),
-        state
-            .chunks(8)
-            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
-            .collect::<Vec<_>>(),
+/// Keccak-p[25 * lane_bits, rounds], the last `rounds` rounds of Keccak-f on a state of 25 lanes
+/// indexed by `x + 5 * y`. Works for every lane size from 1 to 64 bits, only the low `lane_bits`
+/// bits of every lane are used.
+///
+/// ## Panics
+/// if `lane_bits` is not a power of two up to 64 or `rounds > 12 + 2 * log2(lane_bits)`
+pub fn keccak_p(lanes: &mut [u64; 25], lane_bits: u32, rounds: usize) {
+    match lane_bits {
+        1 => keccak_rounds::<1>(lanes, rounds),
+        2 => keccak_rounds::<2>(lanes, rounds),
+        4 => keccak_rounds::<4>(lanes, rounds),
+        8 => keccak_rounds::<8>(lanes, rounds),
+        16 => keccak_rounds::<16>(lanes, rounds),
+        32 => keccak_rounds::<32>(lanes, rounds),
+        64 => keccak_rounds::<64>(lanes, rounds),
+        _ => panic!("The lanes of Keccak are a power of two up to 64 bits"),
+    }
+}
+
+/// Keccak-f[25 * lane_bits], all `12 + 2 * log2(lane_bits)` rounds of Keccak-p
+///
+/// ## Panics
+/// if `lane_bits` is not a power of two up to 64
+pub fn keccak_f(lanes: &mut [u64; 25], lane_bits: u32) {
+    assert!(lane_bits.is_power_of_two() && lane_bits <= 64);
+    keccak_p(
+        lanes,
+        lane_bits,
+        12 + 2 * lane_bits.trailing_zeros() as usize,
     )
-    .unwrap();
-    for i in ROUND_CONSTANTS.iter() {
-        round_1600(&mut array, *i);
+}
+
+/// Keccak-p on a byte state with lanes of `STATE_SIZE / 25` bytes in little endian
+fn keccak_p_bytes<const STATE_SIZE: usize>(state: &mut [u8; STATE_SIZE], rounds: usize) {
+    let lane_bytes = STATE_SIZE / 25;
+    let mut lanes = [0u64; 25];
+    for (lane, bytes) in lanes.iter_mut().zip(state.chunks_exact(lane_bytes)) {
+        *lane = bytes
+            .iter()
+            .rev()
+            .fold(0, |acc, byte| acc << 8 | u64::from(*byte));
     }
-    *state = array
-        .into_raw_vec()
-        .iter()
-        .flat_map(|word| word.to_le_bytes())
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
+    keccak_p(&mut lanes, 8 * lane_bytes as u32, rounds);
+    for (lane, bytes) in lanes.iter().zip(state.chunks_exact_mut(lane_bytes)) {
+        bytes.copy_from_slice(&lane.to_le_bytes()[..lane_bytes]);
+    }
+}
+
+/// Keccak-p[200, ROUNDS] on 8 bit lanes
+pub fn keccak_p_200<const ROUNDS: usize>(state: &mut [u8; 25]) {
+    keccak_p_bytes(state, ROUNDS)
+}
+
+/// Keccak-p[400, ROUNDS] on 16 bit lanes
+pub fn keccak_p_400<const ROUNDS: usize>(state: &mut [u8; 50]) {
+    keccak_p_bytes(state, ROUNDS)
 }
 
-fn round_1600(state: &mut Array2<u64>, round_constant: u64) {
-    theta_step(state);
-    rho_step(state);
+/// Keccak-p[800, ROUNDS] on 32 bit lanes
+pub fn keccak_p_800<const ROUNDS: usize>(state: &mut [u8; 100]) {
+    keccak_p_bytes(state, ROUNDS)
+}
+
+/// Keccak-f[200], 18 rounds
+pub fn keccak_f_200(state: &mut [u8; 25]) {
+    keccak_p_200::<18>(state)
+}
+
+/// Keccak-f[400], 20 rounds
+pub fn keccak_f_400(state: &mut [u8; 50]) {
+    keccak_p_400::<20>(state)
+}
+
+/// Keccak-f[800], 22 rounds
+pub fn keccak_f_800(state: &mut [u8; 100]) {
+    keccak_p_800::<22>(state)
+}
+
+/// Keccak-f[1600], the 24 round permutation of SHA3, on the 25 lanes of the state indexed by
+/// `x + 5 * y`
+pub fn keccak_f_1600(state: &mut [u64; 25]) {
+    keccak_p_1600::<24>(state)
+}
+
+/// Keccak-p[1600, ROUNDS], the last `ROUNDS` rounds of Keccak-f[1600]
+///
+/// ## Panics
+/// if `ROUNDS > 24`
+pub fn keccak_p_1600<const ROUNDS: usize>(state: &mut [u64; 25]) {
+    keccak_rounds::<64>(state, ROUNDS)
+}
+
+/// The last `rounds` rounds of Keccak-f on lanes of `LANE_BITS` bits
+#[inline(always)]
+fn keccak_rounds<const LANE_BITS: u32>(state: &mut [u64; 25], rounds: usize) {
+    let full_rounds = 12 + 2 * LANE_BITS.trailing_zeros() as usize;
+    assert!(
+        rounds <= full_rounds,
+        "Keccak-p can have at most {full_rounds} rounds"
+    );
+    // The round constants of the smaller widths are the 64 bit ones cut to the lane size
+    for round_constant in &ROUND_CONSTANTS[full_rounds - rounds..full_rounds] {
+        round::<LANE_BITS>(state, round_constant & lane_mask::<LANE_BITS>());
+    }
+}
+
+// The steps work on the 25 lanes of the state indexed by `x + 5 * y`, of which only the low
+// `LANE_BITS` bits are used
+
+#[inline(always)]
+fn round<const LANE_BITS: u32>(state: &mut [u64; 25], round_constant: u64) {
+    theta_step::<LANE_BITS>(state);
+    rho_step::<LANE_BITS>(state);
     let aux_state = pi_step(state);
     xi_step(state, &aux_state);
     iota_step(state, round_constant);
 }
 
-fn theta_step(state: &mut Array2<u64>) {
-    let C: Vec<u64> = state
-        .rows()
-        .into_iter()
-        .map(|row| row.fold(0u64, |x, acc| acc ^ x))
-        .collect();
+#[inline(always)]
+const fn lane_mask<const LANE_BITS: u32>() -> u64 {
+    u64::MAX >> (64 - LANE_BITS)
+}
 
-    let D: Vec<u64> = (0..5)
-        .map(|x| C[(x + 4) % 5] ^ (C[(x + 1) % 5]).rotate_left(1))
-        .collect();
-    for ((x, _), A) in state.indexed_iter_mut() {
-        *A ^= D[x];
+/// Rotates the low `LANE_BITS` bits of `lane` to the left
+#[inline(always)]
+fn rotate<const LANE_BITS: u32>(lane: u64, offset: u32) -> u64 {
+    match (LANE_BITS, offset % LANE_BITS) {
+        (64, offset) => lane.rotate_left(offset),
+        (_, 0) => lane,
+        (_, offset) => {
+            let lane = lane & lane_mask::<LANE_BITS>();
+            ((lane << offset) | (lane >> (LANE_BITS - offset))) & lane_mask::<LANE_BITS>()
+        }
     }
 }
 
-fn rho_step(state: &mut Array2<u64>) {
-    let rotation_offsets: Array2<u32> = Array2::from_shape_vec(
-        (5, 5).f(),
-        vec![
-            0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61,
-            56, 14,
-        ],
-    )
-    .unwrap();
-    azip!((a in state, &r in &rotation_offsets) *a = a.rotate_left(r));
+#[inline(always)]
+fn theta_step<const LANE_BITS: u32>(state: &mut [u64; 25]) {
+    let C: [u64; 5] = [
+        state[0] ^ state[5] ^ state[10] ^ state[15] ^ state[20],
+        state[1] ^ state[6] ^ state[11] ^ state[16] ^ state[21],
+        state[2] ^ state[7] ^ state[12] ^ state[17] ^ state[22],
+        state[3] ^ state[8] ^ state[13] ^ state[18] ^ state[23],
+        state[4] ^ state[9] ^ state[14] ^ state[19] ^ state[24],
+    ];
+    let D: [u64; 5] = [
+        C[4] ^ rotate::<LANE_BITS>(C[1], 1),
+        C[0] ^ rotate::<LANE_BITS>(C[2], 1),
+        C[1] ^ rotate::<LANE_BITS>(C[3], 1),
+        C[2] ^ rotate::<LANE_BITS>(C[4], 1),
+        C[3] ^ rotate::<LANE_BITS>(C[0], 1),
+    ];
+    for row in state.chunks_exact_mut(5) {
+        row[0] ^= D[0];
+        row[1] ^= D[1];
+        row[2] ^= D[2];
+        row[3] ^= D[3];
+        row[4] ^= D[4];
+    }
 }
 
-fn pi_step(state: &Array2<u64>) -> Array2<u64> {
-    let mut B: Array2<u64> = Array2::zeros((5, 5));
-    for ((x, y), A) in state.indexed_iter() {
-        *B.index_mut(Ix2(y, (2 * x + 3 * y) % 5)) = *A;
-    }
-    B
+#[inline(always)]
+fn rho_step<const LANE_BITS: u32>(state: &mut [u64; 25]) {
+    state[1] = rotate::<LANE_BITS>(state[1], 1);
+    state[2] = rotate::<LANE_BITS>(state[2], 62);
+    state[3] = rotate::<LANE_BITS>(state[3], 28);
+    state[4] = rotate::<LANE_BITS>(state[4], 27);
+    state[5] = rotate::<LANE_BITS>(state[5], 36);
+    state[6] = rotate::<LANE_BITS>(state[6], 44);
+    state[7] = rotate::<LANE_BITS>(state[7], 6);
+    state[8] = rotate::<LANE_BITS>(state[8], 55);
+    state[9] = rotate::<LANE_BITS>(state[9], 20);
+    state[10] = rotate::<LANE_BITS>(state[10], 3);
+    state[11] = rotate::<LANE_BITS>(state[11], 10);
+    state[12] = rotate::<LANE_BITS>(state[12], 43);
+    state[13] = rotate::<LANE_BITS>(state[13], 25);
+    state[14] = rotate::<LANE_BITS>(state[14], 39);
+    state[15] = rotate::<LANE_BITS>(state[15], 41);
+    state[16] = rotate::<LANE_BITS>(state[16], 45);
+    state[17] = rotate::<LANE_BITS>(state[17], 15);
+    state[18] = rotate::<LANE_BITS>(state[18], 21);
+    state[19] = rotate::<LANE_BITS>(state[19], 8);
+    state[20] = rotate::<LANE_BITS>(state[20], 18);
+    state[21] = rotate::<LANE_BITS>(state[21], 2);
+    state[22] = rotate::<LANE_BITS>(state[22], 61);
+    state[23] = rotate::<LANE_BITS>(state[23], 56);
+    state[24] = rotate::<LANE_BITS>(state[24], 14);
+}
+
+/// Moves the lane at (x, y) to (y, 2x + 3y)
+#[inline(always)]
+fn pi_step(state: &[u64; 25]) -> [u64; 25] {
+    [
+        state[0], state[6], state[12], state[18], state[24], state[3], state[9], state[10],
+        state[16], state[22], state[1], state[7], state[13], state[19], state[20], state[4],
+        state[5], state[11], state[17], state[23], state[2], state[8], state[14], state[15],
+        state[21],
+    ]
 }
 
-fn xi_step(state: &mut Array2<u64>, aux_state: &Array2<u64>) {
-    for ((x, y), A) in state.indexed_iter_mut() {
-        *A = aux_state.index(Ix2(x, y))
-            ^ (!(aux_state.index(Ix2((x + 1) % 5, y))) & aux_state.index(Ix2((x + 2) % 5, y)))
+#[inline(always)]
+fn xi_step(state: &mut [u64; 25], aux_state: &[u64; 25]) {
+    for (row, B) in state.chunks_exact_mut(5).zip(aux_state.chunks_exact(5)) {
+        row[0] = B[0] ^ (!B[1] & B[2]);
+        row[1] = B[1] ^ (!B[2] & B[3]);
+        row[2] = B[2] ^ (!B[3] & B[4]);
+        row[3] = B[3] ^ (!B[4] & B[0]);
+        row[4] = B[4] ^ (!B[0] & B[1]);
     }
 }
 
-fn iota_step(state: &mut Array2<u64>, round_constant: u64) {
-    *state.index_mut(Ix2(0, 0)) ^= round_constant;
+#[inline(always)]
+fn iota_step(state: &mut [u64; 25], round_constant: u64) {
+    state[0] ^= round_constant;
+}
+
+/// Pads a message of `bit_len` bits, where the suffix bits can start in the middle of a byte
+fn keccak_bit_padding(input: &[u8], bit_len: u64, rate: usize, delimited_suffix: u8) -> Vec<u8> {
+    let (full_bytes, last_bits) = ((bit_len / 8) as usize, (bit_len % 8) as u32);
+    let mut padded = input[..full_bytes].to_vec();
+    // The last message bits followed by the suffix and the first bit of the padding
+    let last_byte = match last_bits {
+        0 => 0,
+        _ => u16::from(input[full_bytes]) & ((1 << last_bits) - 1),
+    };
+    let tail = last_byte | (u16::from(delimited_suffix) << last_bits);
+    padded.extend_from_slice(&tail.to_le_bytes()[..if tail > 0xFF { 2 } else { 1 }]);
+    // The last bit of the padding needs a new block if the first one ended the block
+    if padded.len().is_multiple_of(rate) && padded[padded.len() - 1] & 0x80 != 0 {
+        padded.push(0x00);
+    }
+    padded.resize(padded.len().div_ceil(rate) * rate, 0x00);
+    *padded.last_mut().unwrap() |= 0x80;
+    padded
 }
 
 fn keccak_padding(input: &[u8], rate: usize, delimited_suffix: u8) -> Vec<u8> {
@@ -124,8 +409,8 @@ fn keccak_padding(input: &[u8], rate: usize, delimited_suffix: u8) -> Vec<u8> {
             [0x80].as_slice(),
         ]
         .concat(),
-        1 => vec![0x86],
-        2 => vec![0x06, 0x80],
+        1 => vec![delimited_suffix | 0x80],
+        2 => vec![delimited_suffix, 0x80],
         x => [
             [delimited_suffix].as_slice(),
             [0x00].repeat(x - 2).as_slice(),
@@ -149,6 +434,24 @@ fn keccak_padding(input: &[u8], rate: usize, delimited_suffix: u8) -> Vec<u8> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Integrity::Sponge::sponge;
+    use const_hex::encode;
+    use ndarray::prelude::*;
+
+    // The step tests are written on 5x5 arrays, where (x, y) is the lane `x + 5 * y`
+    fn to_lanes(array: &Array2<u64>) -> [u64; 25] {
+        std::array::from_fn(|i| array[[i % 5, i / 5]])
+    }
+
+    fn to_array(lanes: &[u64; 25]) -> Array2<u64> {
+        Array2::from_shape_vec((5, 5).f(), lanes.to_vec()).unwrap()
+    }
+
+    fn on_lanes<F: Fn(&mut [u64; 25])>(array: &mut Array2<u64>, step: F) {
+        let mut lanes = to_lanes(array);
+        step(&mut lanes);
+        *array = to_array(&lanes);
+    }
     #[test]
     fn theta_test() {
         let mut test_array: Array2<u64> = Array2::from_shape_vec(
@@ -159,7 +462,7 @@ mod tests {
             ],
         )
         .unwrap();
-        theta_step(&mut test_array);
+        on_lanes(&mut test_array, theta_step::<64>);
         let should_be_resuling_array: Array2<u64> = Array2::from_shape_vec(
             (5, 5).f(),
             vec![
@@ -178,7 +481,7 @@ mod tests {
             ],
         )
         .unwrap();
-        theta_step(&mut snd_test_array);
+        on_lanes(&mut snd_test_array, theta_step::<64>);
         let should_be_snd_resulting_array: Array2<u64> = Array2::from_shape_vec(
             (5, 5),
             vec![
@@ -199,7 +502,7 @@ mod tests {
             ],
         )
         .unwrap();
-        rho_step(&mut test_array);
+        on_lanes(&mut test_array, rho_step::<64>);
         let should_be_resuling_array: Array2<u64> = Array2::from_shape_vec(
             (5, 5).f(),
             vec![
@@ -241,7 +544,7 @@ mod tests {
             ],
         )
         .unwrap();
-        rho_step(&mut snd_test_array);
+        on_lanes(&mut snd_test_array, rho_step::<64>);
         let should_be_snd_resulting_array: Array2<u64> = Array2::from_shape_vec(
             (5, 5),
             vec![
@@ -285,7 +588,7 @@ mod tests {
             ],
         )
         .unwrap();
-        let aux_state = pi_step(&test_array);
+        let aux_state = to_array(&pi_step(&to_lanes(&test_array)));
         let resulting_array = Array2::from_shape_vec(
             (5, 5),
             vec![
@@ -316,7 +619,9 @@ mod tests {
         )
         .unwrap();
 
-        xi_step(&mut test_array, &aux_state);
+        on_lanes(&mut test_array, |lanes| {
+            xi_step(lanes, &to_lanes(&aux_state))
+        });
         let resulting_array: Array2<u64> = Array2::from_shape_vec(
             (5, 5),
             vec![
@@ -337,7 +642,7 @@ mod tests {
             ],
         )
         .unwrap();
-        iota_step(&mut test_array, 2147516555);
+        on_lanes(&mut test_array, |lanes| iota_step(lanes, 2147516555));
         let resulting_array: Array2<u64> = Array2::from_shape_vec(
             (5, 5).f(),
             vec![
@@ -350,26 +655,141 @@ mod tests {
     }
     #[test]
     fn keccak_f_1600_test() {
-        let mut test_array: [u8; 200] = (0..25u64)
-            .flat_map(|word| word.to_le_bytes())
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+        let mut test_array: [u64; 25] = std::array::from_fn(|i| i as u64);
         keccak_f_1600(&mut test_array);
-        let should_array: [u8; 200] = [
-            21, 129, 237, 82, 82, 176, 116, 131, 0, 148, 86, 182, 118, 166, 247, 29, 125, 121, 81,
-            138, 75, 25, 101, 247, 69, 5, 118, 209, 67, 123, 71, 32, 106, 96, 246, 243, 164, 139,
-            95, 209, 147, 212, 141, 124, 79, 20, 215, 161, 63, 253, 56, 81, 150, 147, 209, 48, 190,
-            227, 27, 149, 114, 148, 126, 72, 90, 122, 218, 203, 88, 168, 243, 12, 136, 127, 177,
-            155, 56, 78, 229, 47, 143, 38, 159, 13, 222, 56, 115, 11, 127, 109, 37, 139, 245, 223,
-            239, 85, 106, 62, 44, 235, 148, 62, 53, 200, 17, 31, 144, 140, 148, 246, 42, 46, 166,
-            157, 48, 202, 12, 222, 115, 232, 226, 49, 77, 148, 108, 194, 175, 247, 215, 21, 196,
-            140, 128, 234, 245, 160, 207, 216, 62, 126, 67, 49, 245, 83, 33, 210, 164, 67, 59, 31,
-            127, 119, 133, 233, 153, 180, 60, 166, 12, 253, 48, 35, 209, 197, 192, 85, 192, 212,
-            223, 167, 224, 166, 138, 229, 47, 167, 163, 72, 153, 124, 147, 245, 26, 66, 136, 8, 52,
-            113, 48, 16, 22, 94, 51, 74, 126, 41, 58, 244, 83, 209,
+        let should_array: [u64; 25] = [
+            0x8374B05252ED8115,
+            0x1DF7A676B6569400,
+            0xF765194B8A51797D,
+            0x20477B43D1760545,
+            0xD15F8BA4F3F6606A,
+            0xA1D7144F7C8DD493,
+            0x30D193965138FD3F,
+            0x487E9472951BE3BE,
+            0x0CF3A858CBDA7A5A,
+            0x2FE54E389BB17F88,
+            0x0B7338DE0D9F268F,
+            0x55EFDFF58B256D7F,
+            0xC8353E94EB2C3E6A,
+            0x2E2AF6948C901F11,
+            0xE873DE0CCA309DA6,
+            0xF7AFC26C944D31E2,
+            0xA0F5EA808CC415D7,
+            0x53F531437E3ED8CF,
+            0x777F1F3B43A4D221,
+            0xFD0CA63CB499E985,
+            0xD4C055C0C5D12330,
+            0xA72FE58AA6E0A7DF,
+            0x421AF5937C9948A3,
+            0x5E16103071340888,
+            0xD153F43A297E4A33,
         ];
-        assert_eq!(should_array.len(), 200);
         assert_eq!(test_array, should_array);
     }
+
+    #[test]
+    fn keccak_f_widths_test() {
+        // Computed with an independent implementation that derives the round constants from the LFSR
+        let expected: [(u32, [u64; 25]); 4] = [
+            (
+                1,
+                [
+                    1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+                ],
+            ),
+            (
+                2,
+                [
+                    3, 3, 2, 3, 2, 2, 1, 1, 2, 0, 3, 1, 0, 3, 3, 3, 0, 1, 2, 1, 3, 1, 0, 1, 2,
+                ],
+            ),
+            (
+                4,
+                [
+                    15, 12, 10, 6, 9, 3, 8, 12, 0, 8, 4, 5, 9, 8, 8, 12, 14, 6, 13, 9, 7, 4, 2, 2,
+                    3,
+                ],
+            ),
+            (
+                8,
+                [
+                    127, 3, 64, 189, 94, 249, 169, 206, 108, 119, 209, 65, 234, 145, 35, 119, 45,
+                    131, 240, 64, 191, 35, 28, 165, 28,
+                ],
+            ),
+        ];
+        for (lane_bits, expected_lanes) in expected {
+            let mut lanes: [u64; 25] =
+                std::array::from_fn(|i| i as u64 & (u64::MAX >> (64 - lane_bits)));
+            keccak_f(&mut lanes, lane_bits);
+            assert_eq!(lanes, expected_lanes);
+        }
+
+        let mut lanes: [u64; 25] = std::array::from_fn(|i| i as u64);
+        keccak_p(&mut lanes, 8, 6);
+        assert_eq!(
+            lanes,
+            [
+                160, 195, 232, 97, 82, 65, 112, 219, 202, 185, 237, 23, 78, 35, 14, 21, 37, 2, 129,
+                221, 238, 153, 147, 135, 198
+            ]
+        );
+    }
+
+    #[test]
+    fn keccak_f_bytes_test() {
+        let mut state = [0x00; 25];
+        keccak_f_200(&mut state);
+        assert_eq!(
+            encode(state),
+            "3c2826841cb35c171eaae9b811134ceaa3852c69d2c5abafea"
+        );
+        let mut state = [0x00; 50];
+        keccak_f_400(&mut state);
+        assert_eq!(
+            encode(state),
+            "f509ac40a90ff5149fe8a0ecd15b7078f0ef8fbf3703526075dcc90e76e74652a159815d956d146e3e63ee58ff714c718eb3"
+        );
+        let mut state = [0x00; 100];
+        keccak_f_800(&mut state);
+        assert_eq!(
+            encode(state),
+            "5dd431e5fbc604f499bfa0232f45f8f142d0ff5178f539e5a7800bf0643697af4cf35abf24247a22152717888458689f54d05cb10efcf41b91fa66619a599e1a1f0a97a3879665ab688dabaf15104be7981a0034f3ef1941760e0a937080b28796e9ef11"
+        );
+        let mut state = [0u64; 25];
+        keccak_p_1600::<12>(&mut state);
+        assert_eq!(
+            encode(state.map(u64::to_le_bytes).concat()),
+            "1786a7b938545e8e1ed059f2506acdd9351fa952c6e7b887c5e0e4cd67e09310455ad9f290ab33b0451adda8722fa7e09c2f6714aa8037c51d075100f547dd3ecc8a170c311da3b3a0aa5792a586b5799bf9b1b33d7c4abc93678ae66340876866250e2e33036c5cda30f0b90212aa9c9f7acf2b789a3b5f2379ae61e0c136e5ec873cb718b6e96dc28a9170f1d1be2ab724edda53bdab6a5ae12e2c6a41c1bfaf5209b936e0cfc6d76070dc17365045e47a9fc2b21156627a64302cdb7136d41ca02c22760dfdcf"
+        );
+    }
+
+    #[test]
+    fn keccak_small_width_sponge_test() {
+        fn check<const STATE_SIZE: usize>(permutation: fn(&mut [u8; STATE_SIZE]), rate: usize) {
+            let input: Vec<u8> = (0..100u8).collect();
+            let expected = sponge::<_, _, 40, STATE_SIZE>(
+                permutation,
+                |pad_input: &[u8], pad_rate: usize| keccak_padding(pad_input, pad_rate, 0x06),
+                rate,
+                &input,
+            );
+            let mut keccak = Keccak::with_permutation(permutation, rate, 0x06);
+            for piece in input.chunks(7) {
+                keccak.update(piece);
+            }
+            assert_eq!(keccak.finalize::<40>(), expected);
+        }
+        check(keccak_f_200, 2);
+        check(keccak_p_200::<12>, 5);
+        check(keccak_f_400, 10);
+        check(keccak_f_800, 36);
+    }
+
+    #[test]
+    #[should_panic]
+    fn keccak_p_too_many_rounds_test() {
+        let mut lanes = [0u64; 25];
+        keccak_p(&mut lanes, 8, 19);
+    }
 }
@@ -5,7 +5,8 @@ use std::ops::*;
 use crate::Confidentiality::AES::sub_byte;
 use PhotonConstants::*;
 
-use super::extended_sponge;
+use super::IncrementalSponge;
+use crate::Integrity::Digest;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum PhotonCell {
@@ -299,117 +300,208 @@ fn mix_columns_serial(state: &mut Array2<PhotonCell>, block_size: PhotonBlockSiz
     }
 }
 
-fn u8_to_photoncell(value: u8, block_size: PhotonBlockSize) -> Vec<PhotonCell> {
-    match block_size {
-        PhotonBlockSize::P288 => vec![PhotonCell::U8(value)],
-        _ => vec![PhotonCell::U4(value >> 4), PhotonCell::U4(value & 0xf)],
+/// Splits bytes into the cells of `block_size`, 4 bit cells take the high nibble first
+fn bytes_to_cells(bytes: &[u8], block_size: PhotonBlockSize) -> Vec<u8> {
+    match block_size.cell_bits() {
+        8 => bytes.to_vec(),
+        _ => bytes
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xf])
+            .collect(),
     }
 }
 
-fn photoncell_to_u8(values: (PhotonCell, PhotonCell), block_size: PhotonBlockSize) -> Vec<u8> {
-    match block_size {
-        PhotonBlockSize::P288 => vec![values.0.value(), values.1.value()],
-        _ => vec![(values.0.value() << 4) + (values.1.value())],
+fn cells_to_bytes(cells: &[u8], block_size: PhotonBlockSize) -> Vec<u8> {
+    match block_size.cell_bits() {
+        8 => cells.to_vec(),
+        _ => cells
+            .chunks_exact(2)
+            .map(|cells| (cells[0] << 4) | cells[1])
+            .collect(),
     }
 }
 
-fn state_to_array<const STATE_SIZE: usize>(
-    state: &[u8; STATE_SIZE],
+fn state_to_array<const CELLS: usize>(
+    state: &[u8; CELLS],
     block_size: PhotonBlockSize,
 ) -> Array2<PhotonCell> {
     let shape = (block_size.array_size(), block_size.array_size());
-    let cellstate: Vec<PhotonCell> = state
-        .iter()
-        .flat_map(|a| u8_to_photoncell(*a, block_size))
-        .collect();
+    let cellstate: Vec<PhotonCell> = state.iter().map(|a| block_size.cell(*a)).collect();
     Array2::from_shape_vec(shape, cellstate)
-        .expect("state_size and block_size do not work with eachother")
+        .expect("CELLS and block_size do not work with eachother")
 }
 
-fn array_to_state<const STATE_SIZE: usize>(
-    array: Array2<PhotonCell>,
-    block_size: PhotonBlockSize,
-) -> [u8; STATE_SIZE] {
-    let cellstate = array.into_raw_vec();
-    let state_vec: Vec<u8> = cellstate
-        .chunks_exact(2)
-        .flat_map(|xs| photoncell_to_u8((xs[0], xs[1]), block_size))
-        .collect();
-    state_vec
+fn array_to_state<const CELLS: usize>(array: Array2<PhotonCell>) -> [u8; CELLS] {
+    let cells: Vec<u8> = array.iter().map(PhotonCell::value).collect();
+    cells
         .try_into()
-        .expect("The array had an odd amount of elements. Implementing either P100 or P196")
+        .expect("CELLS and block_size do not work with eachother")
 }
 
-fn photon_perm<const STATE_SIZE: usize>(state: &mut [u8; STATE_SIZE], block_size: PhotonBlockSize) {
+/// The PHOTON permutation of `block_size` on a state of one cell per element, row by row
+pub(crate) fn photon_perm<const CELLS: usize>(
+    state: &mut [u8; CELLS],
+    block_size: PhotonBlockSize,
+) {
     let mut array: Array2<PhotonCell> = state_to_array(&*state, block_size);
-    for round in 0..12 {
+    for round in 0..NUMBER_OF_ROUNDS {
         add_constant(&mut array, round, block_size);
         sub_cells(&mut array);
         shift_rows(&mut array, block_size);
         mix_columns_serial(&mut array, block_size);
     }
-    *state = array_to_state(array, block_size);
+    *state = array_to_state(array);
 }
 
-fn photon_pad(input: &[u8], rate: usize) -> Vec<u8> {
+/// Appends a 1 bit and then 0 bits up to a whole number of blocks, `input` and `rate` are in cells
+fn photon_pad(input: &[u8], rate: usize, block_size: PhotonBlockSize) -> Vec<u8> {
     let padding_needed = rate - (input.len() % rate);
-    let padding_bytes: Vec<u8> = match padding_needed {
-        0 => [[0x80].to_vec(), [0x00].repeat(rate - 1)].concat(),
-        1 => vec![0x80],
-        x => [[0x80].to_vec(), [0x00].repeat(x - 1)].concat(),
-    };
-    [input, padding_bytes.as_slice()].concat()
-}
-
-fn photon_P144<const STATE_SIZE: usize>(state: &mut [u8; STATE_SIZE]) {
-    photon_perm(state, PhotonBlockSize::P144)
-}
-fn photon_P256<const STATE_SIZE: usize>(state: &mut [u8; STATE_SIZE]) {
-    photon_perm(state, PhotonBlockSize::P256)
-}
-fn photon_P288<const STATE_SIZE: usize>(state: &mut [u8; STATE_SIZE]) {
-    photon_perm(state, PhotonBlockSize::P288)
+    [
+        input,
+        &[1 << (block_size.cell_bits() - 1)],
+        &[0x00].repeat(padding_needed - 1),
+    ]
+    .concat()
 }
 
-fn photon<const HASH_LEN: usize, const STATE_SIZE: usize, F: Fn(&mut [u8; STATE_SIZE])>(
-    input: &[u8],
-    perm_fun: F,
-    absorb_rate: u8,
-    squeeze_rate: u8,
-) -> [u8; HASH_LEN] {
-    let initialization_state: [u8; STATE_SIZE] = [
-        &[0x00].repeat(STATE_SIZE - 3)[..],
-        &[(HASH_LEN / 4) as u8][..],
-        &[absorb_rate][..],
-        &[squeeze_rate][..],
+/// Zero cells followed by the bytes n / 4, r and r', with the digest length and rates in bits
+fn photon_initialization_state<const CELLS: usize>(
+    hash_bits: usize,
+    absorb_bits: usize,
+    squeeze_bits: usize,
+    block_size: PhotonBlockSize,
+) -> [u8; CELLS] {
+    let parameters = bytes_to_cells(
+        &[(hash_bits / 4) as u8, absorb_bits as u8, squeeze_bits as u8],
+        block_size,
+    );
+    [
+        &[0x00].repeat(CELLS - parameters.len())[..],
+        &parameters[..],
     ]
     .concat()
     .try_into()
-    .expect("The initialization rate should be the correct length");
-    extended_sponge::<_, _, HASH_LEN, STATE_SIZE>(
+    .expect("The initialization rate should be the correct length")
+}
+
+/// The sponge of PHOTON-n/r/r', which absorbs and squeezes whole cells
+fn photon_sponge<const CELLS: usize>(
+    perm_fun: fn(&mut [u8; CELLS]),
+    block_size: PhotonBlockSize,
+    hash_bits: usize,
+    absorb_bits: usize,
+    squeeze_bits: usize,
+) -> IncrementalSponge<[u8; CELLS]> {
+    IncrementalSponge::new(
         perm_fun,
-        photon_pad,
-        absorb_rate.into(),
-        squeeze_rate.into(),
-        initialization_state,
-        input,
+        absorb_bits / block_size.cell_bits(),
+        squeeze_bits / block_size.cell_bits(),
+        photon_initialization_state(hash_bits, absorb_bits, squeeze_bits, block_size),
     )
 }
 
-fn photon128(input: &[u8]) -> [u8; 16] {
-    photon::<16, 18, _>(input, photon_P144, 2, 2)
+fn photon_finalize<const HASH_LEN: usize, const CELLS: usize>(
+    sponge: IncrementalSponge<[u8; CELLS]>,
+    block_size: PhotonBlockSize,
+) -> [u8; HASH_LEN] {
+    let mut output = vec![0x00; 8 * HASH_LEN / block_size.cell_bits()];
+    sponge
+        .finalize(|input, rate| photon_pad(input, rate, block_size))
+        .read(&mut output);
+    cells_to_bytes(&output, block_size)
+        .try_into()
+        .expect("The digest is a whole number of bytes")
 }
 
-fn photon224(input: &[u8]) -> [u8; 28] {
-    photon::<28, 32, _>(input, photon_P256, 4, 4)
-}
+/// Generates the one shot function and the incremental struct with `new`, `update` and
+/// `finalize` of a PHOTON-n/r/r' variant, `$absorb_bits` and `$squeeze_bits` are r and r'
+macro_rules! photon_hasher {
+    ($(#[$doc:meta])* $fn_name:ident, $name:ident, $hash_len:literal, $cells:literal, $block_size:expr, $absorb_bits:literal, $squeeze_bits:literal) => {
+        $(#[$doc])*
+        pub fn $fn_name(input: &[u8]) -> [u8; $hash_len] {
+            let mut hasher = $name::new();
+            hasher.update(input);
+            hasher.finalize()
+        }
+
+        #[doc = concat!("Incremental version of `", stringify!($fn_name), "`")]
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: IncrementalSponge<[u8; $cells]>,
+        }
+
+        impl $name {
+            pub fn new() -> $name {
+                $name {
+                    sponge: photon_sponge(
+                        |state| photon_perm(state, $block_size),
+                        $block_size,
+                        8 * $hash_len,
+                        $absorb_bits,
+                        $squeeze_bits,
+                    ),
+                }
+            }
+
+            pub fn update(&mut self, input: &[u8]) {
+                self.sponge.absorb(&bytes_to_cells(input, $block_size))
+            }
+
+            pub fn finalize(self) -> [u8; $hash_len] {
+                photon_finalize(self.sponge, $block_size)
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Digest for $name {
+            const OUTPUT_SIZE: usize = $hash_len;
+            // The rate rounded up to whole bytes
+            const BLOCK_SIZE: usize = ($absorb_bits as usize).div_ceil(8);
+            type Output = [u8; $hash_len];
+
+            fn new() -> Self {
+                $name::new()
+            }
 
-fn photon256(input: &[u8]) -> [u8; 32] {
-    photon::<32, 36, _>(input, photon_P288, 4, 4)
+            fn update(&mut self, input: &[u8]) {
+                $name::update(self, input)
+            }
+
+            fn finalize(self) -> Self::Output {
+                $name::finalize(self)
+            }
+        }
+    };
 }
 
+photon_hasher!(
+    /// PHOTON-80/20/16, an 80 bit digest from the 100 bit permutation
+    photon80, Photon80, 10, 25, PhotonBlockSize::P100, 20, 16
+);
+photon_hasher!(
+    /// PHOTON-128/16/16, a 128 bit digest from the 144 bit permutation
+    photon128, Photon128, 16, 36, PhotonBlockSize::P144, 16, 16
+);
+photon_hasher!(
+    /// PHOTON-160/36/36, a 160 bit digest from the 196 bit permutation
+    photon160, Photon160, 20, 49, PhotonBlockSize::P196, 36, 36
+);
+photon_hasher!(
+    /// PHOTON-224/32/32, a 224 bit digest from the 256 bit permutation
+    photon224, Photon224, 28, 64, PhotonBlockSize::P256, 32, 32
+);
+photon_hasher!(
+    /// PHOTON-256/32/32, a 256 bit digest from the 288 bit permutation with 8 bit cells
+    photon256, Photon256, 32, 36, PhotonBlockSize::P288, 32, 32
+);
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum PhotonBlockSize {
+pub(crate) enum PhotonBlockSize {
     P100,
     P144,
     P196,
@@ -418,6 +510,20 @@ enum PhotonBlockSize {
 }
 
 impl PhotonBlockSize {
+    fn cell_bits(&self) -> usize {
+        match self {
+            PhotonBlockSize::P288 => 8,
+            _ => 4,
+        }
+    }
+
+    fn cell(&self, value: u8) -> PhotonCell {
+        match self {
+            PhotonBlockSize::P288 => PhotonCell::U8(value),
+            _ => PhotonCell::U4(value),
+        }
+    }
+
     fn array_size(&self) -> usize {
         match self {
             PhotonBlockSize::P100 => 5,
@@ -458,8 +564,103 @@ impl PhotonBlockSize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use const_hex::encode;
     use ndarray::ShapeBuilder;
 
+    #[test]
+    fn photon_incremental_test() {
+        let message: Vec<u8> = (0..100u32).map(|x| (x * 7 + 3) as u8).collect();
+        let mut hasher = Photon256::new();
+        for part in message.chunks(7) {
+            hasher.update(part);
+        }
+        assert_eq!(hasher.finalize(), photon256(&message));
+        assert_eq!(Photon128::digest(&message), photon128(&message));
+        assert_eq!(Photon224::digest(&message[..3]), photon224(&message[..3]));
+    }
+
+    #[test]
+    fn photon_test() {
+        let long: Vec<u8> = (0..100u32).map(|x| (x * 7 + 3) as u8).collect();
+        let messages: [&[u8]; 3] = [b"", b"The quick brown fox jumps over the lazy dog", &long];
+        type Hash = fn(&[u8]) -> Vec<u8>;
+        let kats: [(Hash, [&str; 3]); 5] = [
+            (
+                |m| photon80(m).to_vec(),
+                [
+                    "db90ade53b9917729797",
+                    "22a6a7c0071f5046d2e7",
+                    "fab289fa4ed45208e118",
+                ],
+            ),
+            (
+                |m| photon128(m).to_vec(),
+                [
+                    "ab68c00e242d3d14cae579d803ea3c83",
+                    "5929e75d9333b7dd5cb84303ca49b3c4",
+                    "9bb0f4fd7865fd23ddf363814c6faff4",
+                ],
+            ),
+            (
+                |m| photon160(m).to_vec(),
+                [
+                    "fdd693127fcb2a38bebdab01c7765a8e16f4d907",
+                    "91fbd3e919cd56a92cd2990a44dea4d511e948bb",
+                    "9015cc621947cb9235acf142c3cff68eebc34c46",
+                ],
+            ),
+            (
+                |m| photon224(m).to_vec(),
+                [
+                    "67980cd9a71c5daab9025d9472bce0714d4d7268777b109fde04989c",
+                    "f3824b71fcc710a68b5c10866e9c58591d7581587c5d9104c1f69c1f",
+                    "da05a0e2e52e9adc8a744371a5bf29abf7ff4e5e823d8d0f37fb8c06",
+                ],
+            ),
+            (
+                |m| photon256(m).to_vec(),
+                [
+                    "eecb13369cf15ca19ff76c36a6637789199644a9a0b320f41826155ea2e2d6d5",
+                    "aba4e687dad8d33e6edc38ad436e5f7a1b17a6828bdac696ea4067457ab6d7de",
+                    "5a3499f0da9ab3578361603704309119a406260d14cd4517e3709856054cc1ad",
+                ],
+            ),
+        ];
+        for (hash, digests) in kats {
+            for (message, digest) in messages.iter().zip(digests) {
+                assert_eq!(encode(hash(message)), digest);
+            }
+        }
+    }
+
+    // Every mixing matrix is the d-th power of the companion matrix of its serial coefficients
+    #[test]
+    fn mixing_matrix_test() {
+        let serial: [(PhotonBlockSize, &[u8]); 5] = [
+            (PhotonBlockSize::P100, &[1, 2, 9, 9, 2]),
+            (PhotonBlockSize::P144, &[1, 2, 8, 5, 8, 2]),
+            (PhotonBlockSize::P196, &[1, 4, 6, 1, 1, 6, 4]),
+            (PhotonBlockSize::P256, &[2, 4, 2, 11, 2, 8, 5, 6]),
+            (PhotonBlockSize::P288, &[2, 3, 1, 2, 1, 4]),
+        ];
+        for (block_size, coefficients) in serial {
+            let d = block_size.array_size();
+            let companion = Array2::from_shape_fn((d, d), |(i, j)| {
+                if i == d - 1 {
+                    block_size.cell(coefficients[j])
+                } else {
+                    block_size.cell((j == i + 1) as u8)
+                }
+            });
+            let mut matrix =
+                Array2::from_shape_fn((d, d), |(i, j)| block_size.cell((i == j) as u8));
+            for _ in 0..d {
+                matrix = companion.dot(&matrix);
+            }
+            assert_eq!(matrix, block_size.mixing_matrix(), "{block_size:?}");
+        }
+    }
+
     #[test]
     fn photoncell_arith_test() {
         //sum
@@ -0,0 +1,358 @@
+use super::Photon::{photon_perm, PhotonBlockSize};
+use crate::Authenticity::AuthenticationError;
+use crate::Integrity::HMAC::constant_time_eq;
+
+const STATE_BYTES: usize = 32;
+const TAG_BYTES: usize = 16;
+/// Rate of the first block of PHOTON-Beetle-Hash
+const HASH_INITIAL_RATE: usize = 16;
+/// Rate of the other blocks of PHOTON-Beetle-Hash
+const HASH_RATE: usize = 4;
+
+/// The PHOTON 256 bit permutation on bytes, cells take the low nibble first like in the
+/// reference implementation
+fn beetle_perm(state: &mut [u8; STATE_BYTES]) {
+    let mut cells: [u8; 2 * STATE_BYTES] = [0x00; 2 * STATE_BYTES];
+    for (i, cell) in cells.iter_mut().enumerate() {
+        *cell = (state[i / 2] >> (4 * (i % 2))) & 0xf;
+    }
+    photon_perm(&mut cells, PhotonBlockSize::P256);
+    for (byte, cells) in state.iter_mut().zip(cells.chunks_exact(2)) {
+        *byte = cells[0] | (cells[1] << 4);
+    }
+}
+
+/// Adds the domain separation constant to the last 3 bits of the state
+fn xor_constant(state: &mut [u8; STATE_BYTES], constant: u8) {
+    state[STATE_BYTES - 1] ^= constant << 5;
+}
+
+/// XORs `block` into the front of the state, with a 1 bit and 0 bits after it if it is short
+fn xor_padded(state: &mut [u8; STATE_BYTES], block: &[u8], rate: usize) {
+    for (s, b) in state.iter_mut().zip(block) {
+        *s ^= b;
+    }
+    if block.len() < rate {
+        state[block.len()] ^= 0x01;
+    }
+}
+
+/// Absorbs non empty `input` `rate` bytes at a time, permuting before every block
+fn absorb(state: &mut [u8; STATE_BYTES], input: &[u8], rate: usize, constant: u8) {
+    for block in input.chunks(rate) {
+        beetle_perm(state);
+        xor_padded(state, block, rate);
+    }
+    xor_constant(state, constant);
+}
+
+/// The second half of the rate followed by the first half rotated right by one bit, with the
+/// halves read as little endian integers
+fn shuffle(state: &[u8; STATE_BYTES], rate: usize) -> Vec<u8> {
+    let (first, second) = state[..rate].split_at(rate / 2);
+    let rotated = (0..first.len()).map(|i| (first[i] >> 1) | (first[(i + 1) % first.len()] << 7));
+    second.iter().copied().chain(rotated).collect()
+}
+
+/// Encrypts or decrypts non empty `input`, the state always absorbs the plain text
+fn crypt(
+    state: &mut [u8; STATE_BYTES],
+    input: &[u8],
+    rate: usize,
+    constant: u8,
+    encrypt: bool,
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    for block in input.chunks(rate) {
+        beetle_perm(state);
+        let start = output.len();
+        output.extend(shuffle(state, rate).iter().zip(block).map(|(s, b)| s ^ b));
+        let plain_text = match encrypt {
+            true => block,
+            false => &output[start..],
+        };
+        xor_padded(state, plain_text, rate);
+    }
+    xor_constant(state, constant);
+    output
+}
+
+fn tag(state: &mut [u8; STATE_BYTES]) -> [u8; TAG_BYTES] {
+    beetle_perm(state);
+    state[..TAG_BYTES].try_into().unwrap()
+}
+
+/// PHOTON-Beetle-AEAD[rate] without the tag check, gives the output of `crypt` and the tag
+fn photon_beetle_aead(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    input: &[u8],
+    rate: usize,
+    encrypt: bool,
+) -> (Vec<u8>, [u8; TAG_BYTES]) {
+    let mut state: [u8; STATE_BYTES] = [*nonce, *key].concat().try_into().unwrap();
+    if associated_data.is_empty() && input.is_empty() {
+        xor_constant(&mut state, 1);
+        return (Vec::new(), tag(&mut state));
+    }
+
+    let whole_associated_data = associated_data.len().is_multiple_of(rate);
+    let whole_input = input.len().is_multiple_of(rate);
+    let mut output = Vec::new();
+    if !associated_data.is_empty() {
+        let constant = match (!input.is_empty(), whole_associated_data) {
+            (true, true) => 1,
+            (true, false) => 2,
+            (false, true) => 3,
+            (false, false) => 4,
+        };
+        absorb(&mut state, associated_data, rate, constant);
+    }
+    if !input.is_empty() {
+        let constant = match (!associated_data.is_empty(), whole_input) {
+            (true, true) => 1,
+            (true, false) => 2,
+            (false, true) => 5,
+            (false, false) => 6,
+        };
+        output = crypt(&mut state, input, rate, constant, encrypt);
+    }
+    (output, tag(&mut state))
+}
+
+/// Gives the cypher text with the 16 byte tag appended
+fn photon_beetle_encrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    plain_text: &[u8],
+    rate: usize,
+) -> Vec<u8> {
+    let (cypher_text, tag) =
+        photon_beetle_aead(key, nonce, associated_data, plain_text, rate, true);
+    [cypher_text, tag.to_vec()].concat()
+}
+
+fn photon_beetle_decrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    cypher_text: &[u8],
+    rate: usize,
+) -> Result<Vec<u8>, AuthenticationError> {
+    let Some(split) = cypher_text.len().checked_sub(TAG_BYTES) else {
+        return Err(AuthenticationError::TagNotMatchMessage);
+    };
+    let (cypher_text, expected_tag) = cypher_text.split_at(split);
+    let (plain_text, tag) =
+        photon_beetle_aead(key, nonce, associated_data, cypher_text, rate, false);
+    match constant_time_eq(&tag, expected_tag) {
+        true => Ok(plain_text),
+        false => Err(AuthenticationError::TagNotMatchMessage),
+    }
+}
+
+/// PHOTON-Beetle-AEAD[128], gives the cypher text with the 16 byte tag appended.
+/// A nonce must never be used twice with the same key
+pub fn photon_beetle_128_encrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    plain_text: &[u8],
+) -> Vec<u8> {
+    photon_beetle_encrypt(key, nonce, associated_data, plain_text, 16)
+}
+
+/// Decrypts the output of `photon_beetle_128_encrypt`
+///
+/// ## Errors
+/// `TagNotMatchMessage` if the tag is wrong or missing, no plain text is given out then
+pub fn photon_beetle_128_decrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    cypher_text: &[u8],
+) -> Result<Vec<u8>, AuthenticationError> {
+    photon_beetle_decrypt(key, nonce, associated_data, cypher_text, 16)
+}
+
+/// PHOTON-Beetle-AEAD[32], the variant with a 4 byte rate for small devices. Gives the cypher
+/// text with the 16 byte tag appended. A nonce must never be used twice with the same key
+pub fn photon_beetle_32_encrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    plain_text: &[u8],
+) -> Vec<u8> {
+    photon_beetle_encrypt(key, nonce, associated_data, plain_text, 4)
+}
+
+/// Decrypts the output of `photon_beetle_32_encrypt`
+///
+/// ## Errors
+/// `TagNotMatchMessage` if the tag is wrong or missing, no plain text is given out then
+pub fn photon_beetle_32_decrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 16],
+    associated_data: &[u8],
+    cypher_text: &[u8],
+) -> Result<Vec<u8>, AuthenticationError> {
+    photon_beetle_decrypt(key, nonce, associated_data, cypher_text, 4)
+}
+
+/// PHOTON-Beetle-Hash[32], a 256 bit digest. The first 16 bytes go straight into the state,
+/// the rest is absorbed 4 bytes at a time
+pub fn photon_beetle_hash(input: &[u8]) -> [u8; 32] {
+    let mut state: [u8; STATE_BYTES] = [0x00; STATE_BYTES];
+    if input.is_empty() {
+        xor_constant(&mut state, 1);
+    } else if input.len() <= HASH_INITIAL_RATE {
+        xor_padded(&mut state, input, HASH_INITIAL_RATE);
+        let constant = if input.len() < HASH_INITIAL_RATE {
+            1
+        } else {
+            2
+        };
+        xor_constant(&mut state, constant);
+    } else {
+        let (first, rest) = input.split_at(HASH_INITIAL_RATE);
+        state[..HASH_INITIAL_RATE].copy_from_slice(first);
+        let constant = if rest.len().is_multiple_of(HASH_RATE) {
+            1
+        } else {
+            2
+        };
+        absorb(&mut state, rest, HASH_RATE, constant);
+    }
+    [tag(&mut state), tag(&mut state)]
+        .concat()
+        .try_into()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use const_hex::encode;
+
+    // Key, nonce, associated data and message are counting bytes like in the LWC KAT files
+    const KEY: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    fn counting(len: usize) -> Vec<u8> {
+        (0..len as u8).collect()
+    }
+
+    #[test]
+    fn photon_beetle_128_test() {
+        let kats = [
+            (0, 0, "df4e0bac1162408098fa5cf084d8f464"),
+            (0, 1, "a75df91ea594d719d44f29e78e0ae94872"),
+            (1, 0, "e840449949081c5378e01eba6046dbe8"),
+            (16, 16, "879666073f6c9a1eee05fddb79e8a88766e12f1e592ca486e7e2751a94003d91"),
+            (17, 33, "9d20ac68c374b373ef09844d560782737159dc633e756fba4195b3f6b343aa040702adc094e5069492d5afc7f60a521da6"),
+            (32, 20, "29bbcd6b33407b0379eb0a1f75f2280ed67ed15bfbec673132cbcb376858288fb04c175f"),
+        ];
+        for (ad_len, msg_len, expected) in kats {
+            let (ad, msg) = (counting(ad_len), counting(msg_len));
+            let cypher_text = photon_beetle_128_encrypt(&KEY, &KEY, &ad, &msg);
+            assert_eq!(encode(&cypher_text), expected, "AD {ad_len} PT {msg_len}");
+            assert_eq!(
+                photon_beetle_128_decrypt(&KEY, &KEY, &ad, &cypher_text),
+                Ok(msg)
+            );
+        }
+    }
+
+    #[test]
+    fn photon_beetle_32_test() {
+        let kats = [
+            (0, 0, "df4e0bac1162408098fa5cf084d8f464"),
+            (0, 1, "315df91ea594d719d44f29e78e0ae94872"),
+            (1, 0, "e840449949081c5378e01eba6046dbe8"),
+            (4, 4, "3f13735dafd6b207185d4258999ef7ca9fd756b3"),
+            (17, 33, "f8cd449c9317c5e1305df0bbc37643455026fd1d617adf7f3063b0f4588b7cae7bd8e862ece3b2b50e0463e87baaec61c1"),
+            (32, 20, "33d7fbdad65081bca6307c8ef9fcd7c2573d6e55e168aaea7652a9f0c2017731b9274811"),
+        ];
+        for (ad_len, msg_len, expected) in kats {
+            let (ad, msg) = (counting(ad_len), counting(msg_len));
+            let cypher_text = photon_beetle_32_encrypt(&KEY, &KEY, &ad, &msg);
+            assert_eq!(encode(&cypher_text), expected, "AD {ad_len} PT {msg_len}");
+            assert_eq!(
+                photon_beetle_32_decrypt(&KEY, &KEY, &ad, &cypher_text),
+                Ok(msg)
+            );
+        }
+    }
+
+    #[test]
+    fn photon_beetle_reject_test() {
+        let (ad, msg) = (counting(5), counting(21));
+        let cypher_text = photon_beetle_128_encrypt(&KEY, &KEY, &ad, &msg);
+        for i in 0..cypher_text.len() {
+            let mut tampered = cypher_text.clone();
+            tampered[i] ^= 0x80;
+            assert_eq!(
+                photon_beetle_128_decrypt(&KEY, &KEY, &ad, &tampered),
+                Err(AuthenticationError::TagNotMatchMessage)
+            );
+        }
+        assert_eq!(
+            photon_beetle_128_decrypt(&KEY, &KEY, &ad[1..], &cypher_text),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+        assert_eq!(
+            photon_beetle_32_decrypt(&KEY, &KEY, &ad, &cypher_text),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+        assert_eq!(
+            photon_beetle_32_decrypt(&KEY, &KEY, &ad, &cypher_text[..15]),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+    }
+
+    #[test]
+    fn photon_beetle_hash_test() {
+        let kats = [
+            (
+                0,
+                "44a99882fea033566856a27e7f0c94dc84fac7e411b08b890a4a574e3db75d4a",
+            ),
+            (
+                1,
+                "f165ccd18640b9703e96f1bd9a4a4ee32dd4031e4680a1b9890891dcc63468a7",
+            ),
+            (
+                15,
+                "b26e2947b1ebf3d8d6116716cd89f7ee18683feb11cb6bf81f29c7364a1207ba",
+            ),
+            (
+                16,
+                "ab0d1eb0315df8af7f7ae0ac42eaf2f52fb0fdf0904e182dcc796b6cb8d7981a",
+            ),
+            (
+                17,
+                "5a281ad7eb81fb083d05ccd21b78c4bca938af26f20869da29c8f13b7389bc5f",
+            ),
+            (
+                20,
+                "e6470f7fb66345b3db97774832ab07f26dd836b6cd3b28afa74f67404368f54f",
+            ),
+            (
+                21,
+                "a518eef8c72d9d1da6bee187716a9bbf1daff28eaab6b5e89f071259c219f4cf",
+            ),
+            (
+                64,
+                "caa5f259ff5a59c3e53965736ca1652ce2c53677ad60797af4473bba9b48e751",
+            ),
+        ];
+        for (len, expected) in kats {
+            assert_eq!(
+                encode(photon_beetle_hash(&counting(len))),
+                expected,
+                "Len {len}"
+            );
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Runs the designers' known answer files against the PHOTON hashes and PHOTON-Beetle. The PHOTON
+//! files go in `test_vectors/photon` with one `Msg` and `MD` record per message, the NIST LWC KAT
+//! files of PHOTON-Beetle in `test_vectors/lwc` under the directory names of the submission.
+use super::Photon::*;
+use super::PhotonBeetle::*;
+use crate::test_vectors::{load_records, Record};
+use crate::Authenticity::AuthenticationError;
+use const_hex::encode;
+
+/// The records of `test_vectors/<dir>/<name>`
+fn load(dir: &str, name: &str) -> Vec<Record> {
+    load_records(&format!("{dir}/{name}"))
+}
+
+/// `Msg` and `MD` records
+fn run_hash(dir: &str, name: &str, hash: impl Fn(&[u8]) -> Vec<u8>) {
+    for record in load(dir, name) {
+        let message = record.hex("Msg");
+        assert_eq!(
+            encode(hash(&message)),
+            record.field("MD").to_lowercase(),
+            "{dir}/{name} Msg = {}",
+            record.field("Msg")
+        );
+    }
+}
+
+/// `Key`, `Nonce`, `PT`, `AD` and `CT` records, `CT` has the tag appended
+fn run_aead(
+    dir: &str,
+    encrypt: impl Fn(&[u8; 16], &[u8; 16], &[u8], &[u8]) -> Vec<u8>,
+    decrypt: impl Fn(&[u8; 16], &[u8; 16], &[u8], &[u8]) -> Result<Vec<u8>, AuthenticationError>,
+) {
+    let name = "LWC_AEAD_KAT_128_128.txt";
+    for record in load(dir, name) {
+        let key = record.hex("Key").try_into().expect("16 byte key");
+        let nonce = record.hex("Nonce").try_into().expect("16 byte nonce");
+        let (plain_text, associated_data) = (record.hex("PT"), record.hex("AD"));
+        let cypher_text = record.hex("CT");
+        let count = record.field("Count");
+        assert_eq!(
+            encrypt(&key, &nonce, &associated_data, &plain_text),
+            cypher_text,
+            "{dir}/{name} Count = {count}"
+        );
+        assert_eq!(
+            decrypt(&key, &nonce, &associated_data, &cypher_text),
+            Ok(plain_text),
+            "{dir}/{name} Count = {count}"
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs the PHOTON reference files in test_vectors/photon"]
+fn photon_kat_test() {
+    run_hash("photon", "PHOTON-80_20_16.txt", |m| photon80(m).to_vec());
+    run_hash("photon", "PHOTON-128_16_16.txt", |m| photon128(m).to_vec());
+    run_hash("photon", "PHOTON-160_36_36.txt", |m| photon160(m).to_vec());
+    run_hash("photon", "PHOTON-224_32_32.txt", |m| photon224(m).to_vec());
+    run_hash("photon", "PHOTON-256_32_32.txt", |m| photon256(m).to_vec());
+}
+
+#[test]
+#[ignore = "needs the NIST LWC KAT files in test_vectors/lwc"]
+fn photon_beetle_kat_test() {
+    run_aead(
+        "lwc/photonbeetleaead128rate128v1",
+        photon_beetle_128_encrypt,
+        photon_beetle_128_decrypt,
+    );
+    run_aead(
+        "lwc/photonbeetleaead128rate32v1",
+        photon_beetle_32_encrypt,
+        photon_beetle_32_decrypt,
+    );
+    run_hash(
+        "lwc/photonbeetlehash256rate32v1",
+        "LWC_HASH_KAT_256.txt",
+        |m| photon_beetle_hash(m).to_vec(),
+    );
+}
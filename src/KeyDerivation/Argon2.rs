@@ -0,0 +1,580 @@
+use super::KeyDerivationError;
+use crate::Authenticity::AuthenticationError;
+use crate::Integrity::BLAKE::Blake2bParams;
+use crate::Integrity::HMAC::constant_time_eq;
+use std::fmt;
+use zeroize::Zeroizing;
+
+const ARGON2_VERSION: u32 = 0x13;
+const SYNC_POINTS: u32 = 4;
+const BLOCK_WORDS: usize = 128;
+
+type Block = [u64; BLOCK_WORDS];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Argon2Variant {
+    /// Data dependent memory access, fastest but only for settings without side channels
+    Argon2d = 0,
+    /// Data independent memory access
+    Argon2i = 1,
+    /// Argon2i for the first half of the first pass and Argon2d after, recommended for passwords
+    Argon2id = 2,
+}
+
+/// Parameters of Argon2 (RFC 9106). Defaults to the second recommended option of the RFC, Argon2id
+/// with 64 MiB of memory, 3 passes and 4 lanes, giving a 32 byte tag.
+#[derive(Clone)]
+pub struct Argon2Params {
+    variant: Argon2Variant,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+    tag_len: u32,
+    secret: Zeroizing<Vec<u8>>,
+    associated_data: Vec<u8>,
+}
+
+impl Argon2Params {
+    pub fn new(variant: Argon2Variant) -> Argon2Params {
+        Argon2Params {
+            variant,
+            memory_cost: 1 << 16,
+            time_cost: 3,
+            parallelism: 4,
+            tag_len: 32,
+            secret: Zeroizing::new(Vec::new()),
+            associated_data: Vec::new(),
+        }
+    }
+
+    /// Memory size in KiB, at least `8 * parallelism` when hashing. Less than a multiple of
+    /// `4 * parallelism` is rounded down to one.
+    pub fn memory_cost(mut self, memory_cost: u32) -> Self {
+        self.memory_cost = memory_cost;
+        self
+    }
+
+    /// Number of passes over the memory
+    /// ## Panics
+    /// if `time_cost == 0`
+    pub fn time_cost(mut self, time_cost: u32) -> Self {
+        assert_ne!(time_cost, 0);
+        self.time_cost = time_cost;
+        self
+    }
+
+    /// Number of lanes, which are computed one after the other
+    /// ## Panics
+    /// if `parallelism == 0` or `parallelism > 2^24 - 1`
+    pub fn parallelism(mut self, parallelism: u32) -> Self {
+        assert!(0 < parallelism && parallelism < 1 << 24);
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// ## Panics
+    /// if `tag_len < 4`
+    pub fn tag_length(mut self, tag_len: u32) -> Self {
+        assert!(tag_len >= 4);
+        self.tag_len = tag_len;
+        self
+    }
+
+    /// Optional key, which is mixed in with the password
+    pub fn secret(mut self, secret: &[u8]) -> Self {
+        self.secret = Zeroizing::new(secret.to_vec());
+        self
+    }
+
+    pub fn associated_data(mut self, associated_data: &[u8]) -> Self {
+        self.associated_data = associated_data.to_vec();
+        self
+    }
+
+    /// Hashes the password into a tag of the set length. The memory is wiped before it is freed.
+    ///
+    /// ## Errors
+    /// `SaltTooShort` if `salt.len() < 8`<br>
+    /// `InvalidCost` if `memory_cost < 8 * parallelism` or the memory can't be allocated
+    pub fn hash(&self, password: &[u8], salt: &[u8]) -> Result<Vec<u8>, KeyDerivationError> {
+        if salt.len() < 8 {
+            return Err(KeyDerivationError::SaltTooShort);
+        }
+        if u64::from(self.memory_cost) < 8 * u64::from(self.parallelism) {
+            return Err(KeyDerivationError::InvalidCost);
+        }
+        let lanes = self.parallelism as usize;
+        let segment_length = (self.memory_cost / (SYNC_POINTS * self.parallelism)) as usize;
+        let lane_length = segment_length * SYNC_POINTS as usize;
+        let blocks = lane_length
+            .checked_mul(lanes)
+            .ok_or(KeyDerivationError::InvalidCost)?;
+        let mut memory: Zeroizing<Vec<Block>> = Zeroizing::new(Vec::new());
+        memory
+            .try_reserve_exact(blocks)
+            .map_err(|_| KeyDerivationError::InvalidCost)?;
+        memory.resize(blocks, [0; BLOCK_WORDS]);
+
+        let initial_hash = self.initial_hash(password, salt);
+        for lane in 0..lanes {
+            for column in 0..2 {
+                let seed = [
+                    &initial_hash[..],
+                    &(column as u32).to_le_bytes(),
+                    &(lane as u32).to_le_bytes(),
+                ]
+                .concat();
+                memory[lane * lane_length + column] = block_from_bytes(&variable_hash(&seed, 1024));
+            }
+        }
+
+        let mut position = Position {
+            pass: 0,
+            lane: 0,
+            slice: 0,
+            lanes,
+            segment_length,
+            lane_length,
+        };
+        for pass in 0..self.time_cost as usize {
+            for slice in 0..SYNC_POINTS as usize {
+                // The lanes of a slice do not depend on each other
+                for lane in 0..lanes {
+                    position.pass = pass;
+                    position.lane = lane;
+                    position.slice = slice;
+                    self.fill_segment(&mut memory, &position);
+                }
+            }
+        }
+
+        let mut final_block: Block = memory[lane_length - 1];
+        for lane in 1..lanes {
+            xor_block(
+                &mut final_block,
+                &memory[lane * lane_length + lane_length - 1],
+            );
+        }
+        Ok(variable_hash(
+            &block_to_bytes(&final_block),
+            self.tag_len as usize,
+        ))
+    }
+
+    /// Checks in constant time whether `tag` is the hash of the password with these parameters
+    ///
+    /// ## Errors
+    /// `TagNotMatchMessage` if the tag does not match the password or `hash` gives an error
+    pub fn verify(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        tag: &[u8],
+    ) -> Result<(), AuthenticationError> {
+        let matches = self
+            .hash(password, salt)
+            .is_ok_and(|hash| constant_time_eq(&hash, tag));
+        match matches {
+            true => Ok(()),
+            false => Err(AuthenticationError::TagNotMatchMessage),
+        }
+    }
+
+    // H_0 of the RFC, which binds all inputs and parameters
+    fn initial_hash(&self, password: &[u8], salt: &[u8]) -> Vec<u8> {
+        let mut state = Blake2bParams::new().hash_length(64).to_state();
+        for parameter in [
+            self.parallelism,
+            self.tag_len,
+            self.memory_cost,
+            self.time_cost,
+            ARGON2_VERSION,
+            self.variant as u32,
+        ] {
+            state.update(&parameter.to_le_bytes());
+        }
+        for input in [password, salt, &self.secret, &self.associated_data] {
+            state.update(&(input.len() as u32).to_le_bytes());
+            state.update(input);
+        }
+        state.finalize()
+    }
+
+    fn uses_data_independent_addressing(&self, position: &Position) -> bool {
+        match self.variant {
+            Argon2Variant::Argon2d => false,
+            Argon2Variant::Argon2i => true,
+            Argon2Variant::Argon2id => position.pass == 0 && position.slice < 2,
+        }
+    }
+
+    fn fill_segment(&self, memory: &mut [Block], position: &Position) {
+        let data_independent = self.uses_data_independent_addressing(position);
+        let mut address_block: Block = [0; BLOCK_WORDS];
+        let mut input_block: Block = [0; BLOCK_WORDS];
+        if data_independent {
+            input_block[..6].copy_from_slice(&[
+                position.pass as u64,
+                position.lane as u64,
+                position.slice as u64,
+                memory.len() as u64,
+                u64::from(self.time_cost),
+                self.variant as u64,
+            ]);
+        }
+
+        // The first two blocks of every lane are already made from H_0
+        let first_index = if position.pass == 0 && position.slice == 0 {
+            if data_independent {
+                next_addresses(&mut address_block, &mut input_block);
+            }
+            2
+        } else {
+            0
+        };
+
+        let lane_start = position.lane * position.lane_length;
+        for index in first_index..position.segment_length {
+            let column = position.slice * position.segment_length + index;
+            let previous_column = match column {
+                0 => position.lane_length - 1,
+                _ => column - 1,
+            };
+
+            let pseudo_random = if data_independent {
+                if index % BLOCK_WORDS == 0 {
+                    next_addresses(&mut address_block, &mut input_block);
+                }
+                address_block[index % BLOCK_WORDS]
+            } else {
+                memory[lane_start + previous_column][0]
+            };
+
+            let reference_lane = match position.pass == 0 && position.slice == 0 {
+                true => position.lane,
+                false => ((pseudo_random >> 32) % position.lanes as u64) as usize,
+            };
+            let reference_column = position.reference_column(
+                index,
+                pseudo_random as u32,
+                reference_lane == position.lane,
+            );
+
+            let new_block = compress(
+                &memory[lane_start + previous_column],
+                &memory[reference_lane * position.lane_length + reference_column],
+            );
+            // From the second pass on, the new block is xored into the old one
+            if position.pass == 0 {
+                memory[lane_start + column] = new_block;
+            } else {
+                xor_block(&mut memory[lane_start + column], &new_block);
+            }
+        }
+    }
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self::new(Argon2Variant::Argon2id)
+    }
+}
+
+/// Leaves out the secret
+impl fmt::Debug for Argon2Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Argon2Params")
+            .field("variant", &self.variant)
+            .field("memory_cost", &self.memory_cost)
+            .field("time_cost", &self.time_cost)
+            .field("parallelism", &self.parallelism)
+            .field("tag_len", &self.tag_len)
+            .field("secret_len", &self.secret.len())
+            .field("associated_data", &self.associated_data)
+            .finish()
+    }
+}
+
+/// Where in the memory the current segment is
+struct Position {
+    pass: usize,
+    lane: usize,
+    slice: usize,
+    lanes: usize,
+    segment_length: usize,
+    lane_length: usize,
+}
+
+impl Position {
+    // Maps J_1 onto the blocks that may be referenced, preferring the most recent ones
+    fn reference_column(&self, index: usize, pseudo_random: u32, same_lane: bool) -> usize {
+        let finished_blocks = match self.pass {
+            0 => self.slice * self.segment_length,
+            _ => self.lane_length - self.segment_length,
+        };
+        // The block that is being computed and the one before it can not be referenced
+        let reference_area_size = match same_lane {
+            true => finished_blocks + index - 1,
+            false if index == 0 => finished_blocks - 1,
+            false => finished_blocks,
+        } as u64;
+
+        let x = (u64::from(pseudo_random) * u64::from(pseudo_random)) >> 32;
+        let relative_position = reference_area_size - 1 - ((reference_area_size * x) >> 32);
+        let start_position = match self.pass != 0 && self.slice != SYNC_POINTS as usize - 1 {
+            true => (self.slice + 1) * self.segment_length,
+            false => 0,
+        };
+        (start_position + relative_position as usize) % self.lane_length
+    }
+}
+
+// Makes the next 128 pseudo-random numbers of data independent addressing
+fn next_addresses(address_block: &mut Block, input_block: &mut Block) {
+    input_block[6] += 1;
+    *address_block = compress(&[0; BLOCK_WORDS], &compress(&[0; BLOCK_WORDS], input_block));
+}
+
+/// H' of the RFC, a BLAKE2B based hash with any output length
+fn variable_hash(input: &[u8], output_len: usize) -> Vec<u8> {
+    let length_prefix = (output_len as u32).to_le_bytes();
+    let blake2b = |hash_len: usize, parts: &[&[u8]]| -> Vec<u8> {
+        let mut state = Blake2bParams::new().hash_length(hash_len).to_state();
+        for part in parts {
+            state.update(part);
+        }
+        state.finalize()
+    };
+    if output_len <= 64 {
+        return blake2b(output_len, &[&length_prefix, input]);
+    }
+
+    // Chain 64 byte hashes and use the first half of every one but the last
+    let full_hashes = output_len.div_ceil(32) - 2;
+    let mut output: Vec<u8> = Vec::with_capacity(output_len);
+    let mut hash = blake2b(64, &[&length_prefix, input]);
+    output.extend_from_slice(&hash[..32]);
+    for _ in 1..full_hashes {
+        hash = blake2b(64, &[&hash]);
+        output.extend_from_slice(&hash[..32]);
+    }
+    output.extend_from_slice(&blake2b(output_len - 32 * full_hashes, &[&hash]));
+    output
+}
+
+/// The compression function G of the RFC
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r: Block = *x;
+    xor_block(&mut r, y);
+    let mut z: Block = r;
+
+    // Apply the permutation to the rows and then to the columns of the 8x8 matrix of 16 byte registers
+    for row in 0..8 {
+        let mut words: [usize; 16] = [0; 16];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = 16 * row + i;
+        }
+        permutation(&mut z, words);
+    }
+    for column in 0..8 {
+        let mut words: [usize; 16] = [0; 16];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = 2 * column + 16 * (i / 2) + i % 2;
+        }
+        permutation(&mut z, words);
+    }
+
+    xor_block(&mut z, &r);
+    z
+}
+
+/// The BLAKE2B round with the multiplications of BlaMka, on the words at the given indices
+fn permutation(block: &mut Block, words: [usize; 16]) {
+    let [v0, v1, v2, v3, v4, v5, v6, v7, v8, v9, v10, v11, v12, v13, v14, v15] = words;
+    mix(block, v0, v4, v8, v12);
+    mix(block, v1, v5, v9, v13);
+    mix(block, v2, v6, v10, v14);
+    mix(block, v3, v7, v11, v15);
+    mix(block, v0, v5, v10, v15);
+    mix(block, v1, v6, v11, v12);
+    mix(block, v2, v7, v8, v13);
+    mix(block, v3, v4, v9, v14);
+}
+
+#[inline]
+fn blamka(x: u64, y: u64) -> u64 {
+    let product = (x & 0xffffffff) * (y & 0xffffffff);
+    x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+}
+
+#[inline]
+fn mix(block: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+    block[a] = blamka(block[a], block[b]);
+    block[d] = (block[d] ^ block[a]).rotate_right(32);
+
+    block[c] = blamka(block[c], block[d]);
+    block[b] = (block[b] ^ block[c]).rotate_right(24);
+
+    block[a] = blamka(block[a], block[b]);
+    block[d] = (block[d] ^ block[a]).rotate_right(16);
+
+    block[c] = blamka(block[c], block[d]);
+    block[b] = (block[b] ^ block[c]).rotate_right(63);
+}
+
+fn xor_block(block: &mut Block, other: &Block) {
+    for (word, other_word) in block.iter_mut().zip(other) {
+        *word ^= other_word;
+    }
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block: Block = [0; BLOCK_WORDS];
+    for (word, bytes) in block.iter_mut().zip(bytes.chunks(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    block
+}
+
+fn block_to_bytes(block: &Block) -> Vec<u8> {
+    block.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// Argon2id with the default parameters of `Argon2Params` and the given costs
+///
+/// ## Panics
+/// if `time_cost == 0` or `parallelism` is out of range, see `Argon2Params`
+///
+/// ## Errors
+/// See `Argon2Params::hash`
+pub fn argon2id(
+    password: &[u8],
+    salt: &[u8],
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    Argon2Params::new(Argon2Variant::Argon2id)
+        .parallelism(parallelism)
+        .memory_cost(memory_cost)
+        .time_cost(time_cost)
+        .hash(password, salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use const_hex::encode;
+
+    fn rfc9106_params(variant: Argon2Variant) -> Argon2Params {
+        Argon2Params::new(variant)
+            .parallelism(4)
+            .memory_cost(32)
+            .time_cost(3)
+            .tag_length(32)
+            .secret(&[0x03; 8])
+            .associated_data(&[0x04; 12])
+    }
+
+    #[test]
+    fn argon2_rfc9106_test() {
+        let password = [0x01; 32];
+        let salt = [0x02; 16];
+        assert_eq!(
+            encode(
+                rfc9106_params(Argon2Variant::Argon2d)
+                    .hash(&password, &salt)
+                    .unwrap()
+            ),
+            "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"
+        );
+        assert_eq!(
+            encode(
+                rfc9106_params(Argon2Variant::Argon2i)
+                    .hash(&password, &salt)
+                    .unwrap()
+            ),
+            "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"
+        );
+        assert_eq!(
+            encode(
+                rfc9106_params(Argon2Variant::Argon2id)
+                    .hash(&password, &salt)
+                    .unwrap()
+            ),
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+    }
+
+    #[test]
+    fn argon2_verify_test() {
+        let params = Argon2Params::default()
+            .parallelism(2)
+            .memory_cost(64)
+            .time_cost(1);
+        let tag = params.hash(b"password", b"somesalt").unwrap();
+        assert_eq!(params.verify(b"password", b"somesalt", &tag), Ok(()));
+        assert_eq!(
+            params.verify(b"passwore", b"somesalt", &tag),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+        assert_eq!(argon2id(b"password", b"somesalt", 64, 1, 2), Ok(tag));
+    }
+
+    #[test]
+    fn argon2_builder_order_test() {
+        // 16 KiB is too little for the default 4 lanes, but enough for the 2 set after it
+        let memory_first = Argon2Params::default()
+            .memory_cost(16)
+            .parallelism(2)
+            .time_cost(1);
+        let parallelism_first = Argon2Params::default()
+            .parallelism(2)
+            .memory_cost(16)
+            .time_cost(1);
+        assert_eq!(
+            memory_first.hash(b"password", b"somesalt").unwrap(),
+            parallelism_first.hash(b"password", b"somesalt").unwrap()
+        );
+    }
+
+    #[test]
+    fn argon2_too_little_memory_test() {
+        // The parallelism doesn't raise the memory cost set before it
+        let params = Argon2Params::default().memory_cost(32).parallelism(8);
+        assert_eq!(
+            params.hash(b"password", b"somesalt"),
+            Err(KeyDerivationError::InvalidCost)
+        );
+        assert_eq!(
+            params.verify(b"password", b"somesalt", &[]),
+            Err(AuthenticationError::TagNotMatchMessage)
+        );
+    }
+
+    #[test]
+    fn argon2_short_salt_test() {
+        let params = Argon2Params::default().memory_cost(32).time_cost(1);
+        assert_eq!(
+            params.hash(b"password", b"salt"),
+            Err(KeyDerivationError::SaltTooShort)
+        );
+        assert!(params.hash(b"password", b"saltsalt").is_ok());
+    }
+
+    #[test]
+    fn argon2_debug_hides_secret_test() {
+        let debug = format!("{:?}", rfc9106_params(Argon2Variant::Argon2id));
+        assert!(!debug.contains("3, 3"), "{debug}");
+        assert!(debug.contains("secret_len: 8"), "{debug}");
+    }
+
+    #[test]
+    fn variable_hash_test() {
+        // H' gives a prefix of neither shorter nor longer outputs
+        let long = variable_hash(b"input", 100);
+        assert_eq!(long.len(), 100);
+        assert_ne!(variable_hash(b"input", 99)[..], long[..99]);
+        assert_eq!(variable_hash(b"input", 1024).len(), 1024);
+    }
+}
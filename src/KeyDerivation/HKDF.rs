@@ -0,0 +1,155 @@
+use super::KeyDerivationError;
+use crate::Integrity::Digest;
+use crate::Integrity::HMAC::{hmac, Hmac};
+use crate::Integrity::SHA::SHA2::{Sha256, Sha512};
+
+/// The extract step of HKDF (RFC 5869). Concentrates the entropy of the input keying material in a
+/// pseudorandom key. An empty salt is the same as a salt of `D::OUTPUT_SIZE` zero bytes.
+pub fn extract<D: Digest>(salt: &[u8], input_key_material: &[u8]) -> D::Output {
+    hmac::<D>(salt, input_key_material)
+}
+
+/// The expand step of HKDF (RFC 5869). Stretches the pseudorandom key to `output_len` bytes bound to `info`.
+///
+/// ## Errors
+/// `OutputTooLong` if `output_len > 255 * D::OUTPUT_SIZE`
+pub fn expand<D: Digest>(
+    pseudorandom_key: &[u8],
+    info: &[u8],
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    if output_len > 255 * D::OUTPUT_SIZE {
+        return Err(KeyDerivationError::OutputTooLong);
+    }
+    let mut output: Vec<u8> = Vec::with_capacity(output_len);
+    let mut previous_block: Vec<u8> = Vec::new();
+    for counter in 1..=output_len.div_ceil(D::OUTPUT_SIZE) as u8 {
+        let mut mac = Hmac::<D>::new(pseudorandom_key);
+        mac.update(&previous_block);
+        mac.update(info);
+        mac.update(&[counter]);
+        previous_block = mac.finalize().as_ref().to_vec();
+        output.extend_from_slice(&previous_block);
+    }
+    output.truncate(output_len);
+    Ok(output)
+}
+
+/// HKDF (RFC 5869), extract and expand in one go
+///
+/// ## Errors
+/// `OutputTooLong` if `output_len > 255 * D::OUTPUT_SIZE`
+pub fn hkdf<D: Digest>(
+    salt: &[u8],
+    input_key_material: &[u8],
+    info: &[u8],
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    let pseudorandom_key = extract::<D>(salt, input_key_material);
+    expand::<D>(pseudorandom_key.as_ref(), info, output_len)
+}
+
+/// HKDF-SHA256, can give up to 8160 bytes
+pub fn hkdf_sha256(
+    salt: &[u8],
+    input_key_material: &[u8],
+    info: &[u8],
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    hkdf::<Sha256>(salt, input_key_material, info, output_len)
+}
+
+/// HKDF-SHA512, can give up to 16320 bytes
+pub fn hkdf_sha512(
+    salt: &[u8],
+    input_key_material: &[u8],
+    info: &[u8],
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    hkdf::<Sha512>(salt, input_key_material, info, output_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Integrity::SHA::SHA1::Sha1;
+    use const_hex::encode;
+
+    #[test]
+    fn hkdf_rfc5869_test() {
+        // Test case 1
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        let prk = extract::<Sha256>(&salt, &ikm);
+        assert_eq!(
+            encode(prk),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+        assert_eq!(
+            encode(expand::<Sha256>(&prk, &info, 42).unwrap()),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+
+        // Test case 2
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+        assert_eq!(
+            encode(hkdf_sha256(&salt, &ikm, &info, 82).unwrap()),
+            "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87"
+        );
+
+        // Test case 3, empty salt and info
+        assert_eq!(
+            encode(extract::<Sha256>(&[], &[0x0b; 22])),
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04"
+        );
+        assert_eq!(
+            encode(hkdf_sha256(&[], &[0x0b; 22], &[], 42).unwrap()),
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+        );
+
+        // Test case 4
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        assert_eq!(
+            encode(hkdf::<Sha1>(&salt, &[0x0b; 11], &info, 42).unwrap()),
+            "085a01ea1b10f36933068b56efa5ad81a4f14b822f5b091568a9cdd4f155fda2c22e422478d305f3f896"
+        );
+    }
+
+    #[test]
+    fn hkdf_sha512_test() {
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        assert_eq!(
+            encode(extract::<Sha512>(&salt, &[0x0b; 22])),
+            "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237"
+        );
+        assert_eq!(
+            encode(hkdf_sha512(&salt, &[0x0b; 22], &info, 42).unwrap()),
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb"
+        );
+    }
+
+    #[test]
+    fn hkdf_output_length_test() {
+        assert_eq!(
+            hkdf_sha256(b"salt", b"ikm", b"", 0).unwrap(),
+            Vec::<u8>::new()
+        );
+        assert_eq!(
+            hkdf_sha256(b"salt", b"ikm", b"", 255 * 32).unwrap().len(),
+            8160
+        );
+        assert_eq!(
+            hkdf_sha256(b"salt", b"ikm", b"", 255 * 32 + 1),
+            Err(KeyDerivationError::OutputTooLong)
+        );
+        assert_eq!(
+            hkdf_sha512(b"salt", b"ikm", b"", 255 * 64 + 1),
+            Err(KeyDerivationError::OutputTooLong)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use super::KeyDerivationError;
+use crate::Integrity::Digest;
+use crate::Integrity::HMAC::Hmac;
+use crate::Integrity::SHA::SHA1::Sha1;
+use crate::Integrity::SHA::SHA2::{Sha256, Sha512};
+
+/// PBKDF2 (RFC 8018) with HMAC over the hash function `D` as the pseudorandom function.
+/// Derives `output_len` bytes from the password, every block takes `iterations` HMAC calls.
+///
+/// ## Errors
+/// `ZeroIterations` if `iterations == 0`<br>
+/// `OutputTooLong` if `output_len > (2^32 - 1) * D::OUTPUT_SIZE`
+pub fn pbkdf2<D: Digest>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    if iterations == 0 {
+        return Err(KeyDerivationError::ZeroIterations);
+    }
+    if output_len as u64 > u64::from(u32::MAX) * D::OUTPUT_SIZE as u64 {
+        return Err(KeyDerivationError::OutputTooLong);
+    }
+
+    // The password is the HMAC key of every call, so the keyed state only has to be made once
+    let keyed_mac = Hmac::<D>::new(password);
+    let mut output: Vec<u8> = Vec::with_capacity(output_len);
+    for block_index in 1..=output_len.div_ceil(D::OUTPUT_SIZE) as u32 {
+        let mut mac = keyed_mac.clone();
+        mac.update(salt);
+        mac.update(&block_index.to_be_bytes());
+        let mut previous = mac.finalize();
+        let mut block = previous;
+        for _ in 1..iterations {
+            let mut mac = keyed_mac.clone();
+            mac.update(previous.as_ref());
+            previous = mac.finalize();
+            for (b, p) in block.as_mut().iter_mut().zip(previous.as_ref()) {
+                *b ^= p;
+            }
+        }
+        output.extend_from_slice(block.as_ref());
+    }
+    output.truncate(output_len);
+    Ok(output)
+}
+
+/// PBKDF2 with HMAC-SHA1
+pub fn pbkdf2_sha1(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    pbkdf2::<Sha1>(password, salt, iterations, output_len)
+}
+
+/// PBKDF2 with HMAC-SHA256
+pub fn pbkdf2_sha256(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    pbkdf2::<Sha256>(password, salt, iterations, output_len)
+}
+
+/// PBKDF2 with HMAC-SHA512
+pub fn pbkdf2_sha512(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    pbkdf2::<Sha512>(password, salt, iterations, output_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use const_hex::encode;
+
+    #[test]
+    fn pbkdf2_rfc6070_test() {
+        assert_eq!(
+            encode(pbkdf2_sha1(b"password", b"salt", 1, 20).unwrap()),
+            "0c60c80f961f0e71f3a9b524af6012062fe037a6"
+        );
+        assert_eq!(
+            encode(pbkdf2_sha1(b"password", b"salt", 2, 20).unwrap()),
+            "ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957"
+        );
+        assert_eq!(
+            encode(pbkdf2_sha1(b"password", b"salt", 4096, 20).unwrap()),
+            "4b007901b765489abead49d926f721d065a429c1"
+        );
+        assert_eq!(
+            encode(
+                pbkdf2_sha1(
+                    b"passwordPASSWORDpassword",
+                    b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                    4096,
+                    25
+                )
+                .unwrap()
+            ),
+            "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"
+        );
+        assert_eq!(
+            encode(pbkdf2_sha1(b"pass\0word", b"sa\0lt", 4096, 16).unwrap()),
+            "56fa6aa75548099dcc37d7f03425e0c3"
+        );
+    }
+
+    #[test]
+    fn pbkdf2_rfc7914_test() {
+        assert_eq!(
+            encode(pbkdf2_sha256(b"passwd", b"salt", 1, 64).unwrap()),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+        assert_eq!(
+            encode(pbkdf2_sha256(b"Password", b"NaCl", 80000, 64).unwrap()),
+            "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d"
+        );
+    }
+
+    #[test]
+    fn pbkdf2_sha512_test() {
+        assert_eq!(
+            encode(pbkdf2_sha512(b"password", b"salt", 1000, 100).unwrap()),
+            "afe6c5530785b6cc6b1c6453384731bd5ee432ee549fd42fb6695779ad8a1c5bf59de69c48f774efc4007d5298f9033c0241d5ab69305e7b64eceeb8d834cfec6afdec3c1c23982a121f2d4be008889378a49a0dfb104f0d2856e38f44271cdaf6de4341"
+        );
+    }
+
+    #[test]
+    fn pbkdf2_zero_iterations_test() {
+        assert_eq!(
+            pbkdf2_sha256(b"password", b"salt", 0, 32),
+            Err(KeyDerivationError::ZeroIterations)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use super::KeyDerivationError;
+use super::PBKDF2::pbkdf2_sha256;
+
+/// The Salsa20 core with 8 rounds, a hash function from 64 bytes to 64 bytes
+fn salsa20_8(block: &mut [u32; 16]) {
+    let input: [u32; 16] = *block;
+    let x = block;
+    let quarter_round = |x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+    for _ in 0..4 {
+        // Columns
+        quarter_round(x, 0, 4, 8, 12);
+        quarter_round(x, 5, 9, 13, 1);
+        quarter_round(x, 10, 14, 2, 6);
+        quarter_round(x, 15, 3, 7, 11);
+        // Rows
+        quarter_round(x, 0, 1, 2, 3);
+        quarter_round(x, 5, 6, 7, 4);
+        quarter_round(x, 10, 11, 8, 9);
+        quarter_round(x, 15, 12, 13, 14);
+    }
+    for (word, input_word) in x.iter_mut().zip(input) {
+        *word = word.wrapping_add(input_word);
+    }
+}
+
+/// scryptBlockMix, on 2 * r blocks of 16 words
+fn block_mix(input: &[u32], output: &mut [u32]) {
+    let blocks = input.len() / 16;
+    let mut x: [u32; 16] = input[input.len() - 16..].try_into().unwrap();
+    for (i, block) in input.chunks_exact(16).enumerate() {
+        for (x_word, word) in x.iter_mut().zip(block) {
+            *x_word ^= word;
+        }
+        salsa20_8(&mut x);
+        // Even blocks go to the first half of the output and odd blocks to the second half
+        let position = (i / 2 + (i % 2) * blocks / 2) * 16;
+        output[position..position + 16].copy_from_slice(&x);
+    }
+}
+
+/// scryptROMix, the sequential memory-hard part which uses `n * 128 * r` bytes of memory
+fn ro_mix(block: &mut [u32], n: u64) {
+    let block_words = block.len();
+    let mut x: Vec<u32> = block.to_vec();
+    let mut y: Vec<u32> = vec![0; block_words];
+    let mut v: Vec<u32> = Vec::with_capacity(block_words * n as usize);
+    for _ in 0..n {
+        v.extend_from_slice(&x);
+        block_mix(&x, &mut y);
+        std::mem::swap(&mut x, &mut y);
+    }
+    for _ in 0..n {
+        // Integerify, the first 8 bytes of the last 64 byte block as a little endian number
+        let last_block = block_words - 16;
+        let j = (u64::from(x[last_block]) | u64::from(x[last_block + 1]) << 32) & (n - 1);
+        let j = j as usize;
+        for (x_word, v_word) in x.iter_mut().zip(&v[j * block_words..(j + 1) * block_words]) {
+            *x_word ^= v_word;
+        }
+        block_mix(&x, &mut y);
+        std::mem::swap(&mut x, &mut y);
+    }
+    block.copy_from_slice(&x);
+}
+
+/// scrypt (RFC 7914). `n` is the CPU/memory cost, `r` the block size and `p` the parallelization.
+/// Uses about `128 * n * r` bytes of memory.
+///
+/// ## Errors
+/// `InvalidCost` if `n` is not a power of two bigger than 1 or `n >= 2^(16 * r)`<br>
+/// `InvalidCost` if `r == 0`, `p == 0`, `r * p >= 2^30` or the memory does not fit in a `usize`<br>
+/// `OutputTooLong` if `output_len > (2^32 - 1) * 32`
+pub fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    n: u64,
+    r: u32,
+    p: u32,
+    output_len: usize,
+) -> Result<Vec<u8>, KeyDerivationError> {
+    // Every u64 is below 2^(16 * r) once r >= 4, so the shift only happens when it can't overflow
+    let valid_n = n > 1 && n.is_power_of_two() && (r >= 4 || n < 1 << (16 * r));
+    let valid_r_p = r > 0 && p > 0 && u64::from(r) * u64::from(p) < 1 << 30;
+    if !valid_n || !valid_r_p {
+        return Err(KeyDerivationError::InvalidCost);
+    }
+    // The p blocks and the n blocks of ro_mix have to be addressable
+    let block_len = (r as usize)
+        .checked_mul(128)
+        .ok_or(KeyDerivationError::InvalidCost)?;
+    let blocks_len = block_len
+        .checked_mul(p as usize)
+        .ok_or(KeyDerivationError::InvalidCost)?;
+    usize::try_from(n)
+        .ok()
+        .and_then(|n| n.checked_mul(block_len))
+        .ok_or(KeyDerivationError::InvalidCost)?;
+
+    let mut blocks = pbkdf2_sha256(password, salt, 1, blocks_len)?;
+    for block in blocks.chunks_exact_mut(block_len) {
+        let mut words: Vec<u32> = block
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        ro_mix(&mut words, n);
+        for (bytes, word) in block.chunks_exact_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+    pbkdf2_sha256(password, &blocks, 1, output_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use const_hex::{const_decode_to_array, encode};
+
+    fn words(bytes: &[u8]) -> Vec<u32> {
+        bytes
+            .chunks(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn salsa20_8_test() {
+        // RFC 7914 section 8
+        let input: [u8; 64] = const_decode_to_array(b"7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1dee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e").unwrap();
+        let expected: [u8; 64] = const_decode_to_array(b"a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81").unwrap();
+        let mut block: [u32; 16] = words(&input).try_into().unwrap();
+        salsa20_8(&mut block);
+        assert_eq!(block.to_vec(), words(&expected));
+    }
+
+    #[test]
+    fn scrypt_rfc7914_test() {
+        assert_eq!(
+            encode(scrypt(b"", b"", 16, 1, 1, 64).unwrap()),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+        assert_eq!(
+            encode(scrypt(b"password", b"NaCl", 1024, 8, 16, 64).unwrap()),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+        assert_eq!(
+            encode(scrypt(b"pleaseletmein", b"SodiumChloride", 16384, 8, 1, 64).unwrap()),
+            "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"
+        );
+    }
+
+    #[test]
+    fn scrypt_bad_cost_test() {
+        for (n, r, p) in [
+            (1000, 8, 1),
+            (1, 8, 1),
+            (0, 8, 1),
+            (1 << 16, 1, 1),
+            (16, 0, 1),
+            (16, 1, 0),
+            (16, 1 << 15, 1 << 15),
+            // 128 * n * r bytes of memory overflow a usize
+            (1 << 62, 4, 1),
+        ] {
+            assert_eq!(
+                scrypt(b"password", b"salt", n, r, p, 32),
+                Err(KeyDerivationError::InvalidCost),
+                "n = {n}, r = {r}, p = {p}"
+            );
+        }
+    }
+}
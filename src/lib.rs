@@ -9,10 +9,37 @@
 #![feature(array_chunks, iter_map_windows)]
 
 pub mod Integrity {
+    use std::fmt::Debug;
+
     pub mod BLAKE;
+    pub mod HMAC;
     pub mod SHA;
     pub mod Sponge;
     mod Merkle_Damgard;
+
+    /// A hash function with a fixed digest length, which can be fed its input in pieces.
+    /// Lets code be generic over the hash function, e.g. `fn checksum<D: Digest>(data: &[u8]) -> D::Output`.
+    pub trait Digest: Clone + Default {
+        /// Digest length in bytes
+        const OUTPUT_SIZE: usize;
+        /// Size in bytes of the blocks the input is processed in
+        const BLOCK_SIZE: usize;
+        /// Byte array of `OUTPUT_SIZE` bytes
+        type Output: AsRef<[u8]> + AsMut<[u8]> + Copy + Debug + Eq;
+
+        fn new() -> Self;
+
+        fn update(&mut self, input: &[u8]);
+
+        fn finalize(self) -> Self::Output;
+
+        /// Hashes the whole input at once
+        fn digest(input: &[u8]) -> Self::Output {
+            let mut hasher = Self::new();
+            hasher.update(input);
+            hasher.finalize()
+        }
+    }
 }
 
 pub mod Confidentiality {
@@ -27,6 +54,7 @@ pub mod Authenticity {
     pub mod Ed25519;
 
     #[derive(Debug, Error, PartialEq, Eq, Clone, Copy)]
+    #[non_exhaustive]
     pub enum AuthenticationError {
         #[error("Signature was not a valid signature")]
         BadSignature,
@@ -34,8 +62,35 @@ pub mod Authenticity {
         BadPublicKey,
         #[error("Signature did not match the given message and Public key")]
         SignatureNotMatchMessage,
+        #[error("Tag did not match the given message and key")]
+        TagNotMatchMessage,
+    }
+}
+
+pub mod KeyDerivation {
+    use thiserror::Error;
+
+    pub mod Argon2;
+    pub mod HKDF;
+    pub mod PBKDF2;
+    pub mod Scrypt;
+
+    #[derive(Debug, Error, PartialEq, Eq, Clone, Copy)]
+    pub enum KeyDerivationError {
+        #[error("Requested more output than the key derivation function can give")]
+        OutputTooLong,
+        #[error("The key derivation function needs at least one iteration")]
+        ZeroIterations,
+        #[error("The cost parameters are out of range or need more memory than can be addressed")]
+        InvalidCost,
+        #[error("The salt is shorter than the key derivation function allows")]
+        SaltTooShort,
     }
 }
 
 pub mod Block_cypher;
-mod Feistel_network;
\ No newline at end of file
+mod Feistel_network;
+#[cfg(test)]
+mod test_vectors;
+#[cfg(test)]
+mod wycheproof;
\ No newline at end of file
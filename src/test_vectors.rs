@@ -0,0 +1,97 @@
+//! Reads the vector files in `test_vectors`, for the runners of the different formats
+use const_hex::decode;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The text of `test_vectors/<path>`
+pub(crate) fn read(path: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_vectors")
+        .join(path);
+    fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("Can't read {}: {error}", path.display()))
+}
+
+/// A `name = value` group of a CAVP or LWC KAT file with the `[name = value]` parameters in effect for it
+pub(crate) struct Record {
+    params: HashMap<String, String>,
+    fields: HashMap<String, String>,
+}
+
+impl Record {
+    pub(crate) fn field(&self, name: &str) -> &str {
+        self.fields
+            .get(name)
+            .unwrap_or_else(|| panic!("Record without {name}"))
+    }
+
+    pub(crate) fn hex(&self, name: &str) -> Vec<u8> {
+        decode(self.field(name)).unwrap()
+    }
+
+    pub(crate) fn number(&self, name: &str) -> usize {
+        self.field(name).parse().unwrap()
+    }
+
+    pub(crate) fn param(&self, name: &str) -> usize {
+        self.params
+            .get(name)
+            .unwrap_or_else(|| panic!("Record without [{name}]"))
+            .parse()
+            .unwrap()
+    }
+}
+
+/// Splits a `name = value` file into records, comments and parameters without a value are skipped
+fn parse_rsp(text: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut params = HashMap::new();
+    let mut fields = HashMap::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            if !fields.is_empty() {
+                records.push(Record {
+                    params: params.clone(),
+                    fields: std::mem::take(&mut fields),
+                });
+            }
+            if let Some((name, value)) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|line| line.split_once('='))
+            {
+                params.insert(name.trim().to_owned(), value.trim().to_owned());
+            }
+        } else if let Some((name, value)) = line.split_once('=') {
+            fields.insert(name.trim().to_owned(), value.trim().to_owned());
+        } else {
+            panic!("Malformed line {line:?}");
+        }
+    }
+    if !fields.is_empty() {
+        records.push(Record { params, fields });
+    }
+    records
+}
+
+/// The records of the `name = value` file `test_vectors/<path>`
+pub(crate) fn load_records(path: &str) -> Vec<Record> {
+    let records = parse_rsp(&read(path));
+    assert!(!records.is_empty(), "{path} has no records");
+    records
+}
+
+#[test]
+fn parse_rsp_test() {
+    let records = parse_rsp(
+        "#  comment\n\n[L = 20]\n\nLen = 0\nMsg = 00\nMD = ab\n\n[Input Length = 128]\nCOUNT = 0\nMsg = 01\n",
+    );
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].number("Len"), 0);
+    assert_eq!(records[0].hex("Msg"), [0]);
+    assert_eq!(records[0].param("L"), 20);
+    assert_eq!(records[1].param("L"), 20);
+    assert_eq!(records[1].param("Input Length"), 128);
+    assert_eq!(records[1].field("COUNT"), "0");
+}
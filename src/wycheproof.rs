@@ -0,0 +1,263 @@
+//! Runs Project Wycheproof JSON test vectors from `test_vectors/wycheproof`
+use crate::test_vectors::read;
+use crate::Authenticity::Ed25519::Ed25519_verify_sign;
+use crate::Block_cypher::{BlockCypher, CBC};
+use crate::Confidentiality::AES::AES;
+use crate::Integrity::Sponge::PhotonBeetle::{
+    photon_beetle_128_decrypt, photon_beetle_128_encrypt,
+};
+use const_hex::decode;
+use serde_json::{json, Value};
+
+fn field<'a>(value: &'a Value, name: &str) -> &'a Value {
+    value
+        .get(name)
+        .unwrap_or_else(|| panic!("JSON without {name:?}"))
+}
+
+fn str<'a>(value: &'a Value, name: &str) -> &'a str {
+    field(value, name)
+        .as_str()
+        .unwrap_or_else(|| panic!("{name:?} isn't a string"))
+}
+
+fn array<'a>(value: &'a Value, name: &str) -> &'a [Value] {
+    field(value, name)
+        .as_array()
+        .unwrap_or_else(|| panic!("{name:?} isn't an array"))
+}
+
+fn hex(value: &Value, name: &str) -> Vec<u8> {
+    decode(str(value, name)).unwrap()
+}
+
+/// The test groups of `test_vectors/wycheproof/<name>`
+fn load(name: &str) -> Vec<Value> {
+    let file: Value = serde_json::from_str(&read(&format!("wycheproof/{name}")))
+        .unwrap_or_else(|error| panic!("{name} isn't JSON: {error}"));
+    let groups = array(&file, "testGroups").to_vec();
+    let tests: usize = groups.iter().map(|group| array(group, "tests").len()).sum();
+    assert_eq!(
+        Some(tests as u64),
+        field(&file, "numberOfTests").as_u64(),
+        "{name} has a wrong numberOfTests"
+    );
+    groups
+}
+
+/// Checks whether a test case was accepted against its `result`, `acceptable` cases may go either way
+fn check_result(name: &str, test: &Value, accepted: bool) {
+    let expected = match str(test, "result") {
+        "valid" => true,
+        "invalid" => false,
+        "acceptable" => return,
+        other => panic!("Unknown result {other:?}"),
+    };
+    assert_eq!(
+        accepted,
+        expected,
+        "{name} tcId {} ({}) was {}",
+        field(test, "tcId"),
+        str(test, "comment"),
+        if accepted { "accepted" } else { "rejected" }
+    );
+}
+
+/// `IndCpaTest` groups, `crypt(key, iv, msg, ct)` gives the encryption of `msg` and the
+/// decryption of `ct` or `None` if `ct` is rejected
+fn run_ind_cpa(
+    name: &str,
+    crypt: impl Fn(&[u8], [u8; 16], &[u8], &[u8]) -> (Vec<u8>, Option<Vec<u8>>),
+) {
+    for group in load(name) {
+        for test in array(&group, "tests") {
+            let iv = match test.get("iv") {
+                Some(_) => hex(test, "iv").try_into().expect("AES has a 16 byte iv"),
+                None => [0; 16],
+            };
+            let (msg, ct) = (hex(test, "msg"), hex(test, "ct"));
+            let (encrypted, decrypted) = crypt(&hex(test, "key"), iv, &msg, &ct);
+            check_result(name, test, decrypted.is_some());
+            if decrypted.is_some() {
+                assert_eq!(decrypted, Some(msg), "{name} tcId {}", field(test, "tcId"));
+                assert_eq!(encrypted, ct, "{name} tcId {}", field(test, "tcId"));
+            }
+        }
+    }
+}
+
+/// `AeadTest` groups, `seal(key, iv, aad, msg)` gives the cypher text followed by the tag and
+/// `open(key, iv, aad, ct_and_tag)` the message, both are `None` if the sizes aren't supported or
+/// the cypher text is rejected
+fn run_aead(
+    name: &str,
+    groups: &[Value],
+    seal: impl Fn(&[u8], &[u8], &[u8], &[u8]) -> Option<Vec<u8>>,
+    open: impl Fn(&[u8], &[u8], &[u8], &[u8]) -> Option<Vec<u8>>,
+) {
+    for group in groups {
+        for test in array(group, "tests") {
+            let (key, iv, aad) = (hex(test, "key"), hex(test, "iv"), hex(test, "aad"));
+            let msg = hex(test, "msg");
+            let sealed = [hex(test, "ct"), hex(test, "tag")].concat();
+            let opened = open(&key, &iv, &aad, &sealed);
+            check_result(name, test, opened.is_some());
+            if opened.is_some() {
+                assert_eq!(
+                    opened,
+                    Some(msg.clone()),
+                    "{name} tcId {}",
+                    field(test, "tcId")
+                );
+                assert_eq!(
+                    seal(&key, &iv, &aad, &msg),
+                    Some(sealed),
+                    "{name} tcId {}",
+                    field(test, "tcId")
+                );
+            }
+        }
+    }
+}
+
+/// Calls `$mode::<KEY_SIZE>` with the AES key size of `$key`
+macro_rules! with_aes_key {
+    ($mode:ident, $key:expr, $($arg:expr),*) => {
+        match $key.len() {
+            16 => $mode::<16>($key.try_into().unwrap(), $($arg),*),
+            24 => $mode::<24>($key.try_into().unwrap(), $($arg),*),
+            32 => $mode::<32>($key.try_into().unwrap(), $($arg),*),
+            len => panic!("{len} byte AES key"),
+        }
+    };
+}
+
+/// `None` if `bytes` isn't a whole number of blocks
+fn to_blocks(bytes: &[u8]) -> Option<Vec<[u8; 16]>> {
+    let (blocks, remainder) = bytes.as_chunks::<16>();
+    remainder.is_empty().then(|| blocks.to_vec())
+}
+
+fn cbc<const KEY_SIZE: usize>(
+    key: [u8; KEY_SIZE],
+    iv: [u8; 16],
+    msg: &[u8],
+    ct: &[u8],
+) -> (Vec<u8>, Option<Vec<u8>>)
+where
+    AES: BlockCypher<16, KEY_SIZE>,
+{
+    let decrypted = to_blocks(ct).and_then(|blocks| AES::cbc_try_decrypt(&key, &blocks, iv).ok());
+    (AES::cbc_encrypt(&key, msg, iv).concat(), decrypted)
+}
+
+/// PHOTON-Beetle-AEAD[128] cases with key and nonce 00..0f: an empty message and 16 bytes of
+/// counting aad and message, each with its tag and then with a corrupted tag
+fn photon_beetle_groups(results: [&str; 4]) -> Vec<Value> {
+    let counting = "000102030405060708090a0b0c0d0e0f";
+    let cases = [
+        ("", "", "", "df4e0bac1162408098fa5cf084d8f464"),
+        ("", "", "", "df4e0bac1162408098fa5cf084d8f465"),
+        (
+            counting,
+            counting,
+            "879666073f6c9a1eee05fddb79e8a887",
+            "66e12f1e592ca486e7e2751a94003d91",
+        ),
+        (
+            counting,
+            counting,
+            "879666073f6c9a1eee05fddb79e8a887",
+            "76e12f1e592ca486e7e2751a94003d91",
+        ),
+    ];
+    let tests: Vec<Value> = cases
+        .iter()
+        .zip(results)
+        .enumerate()
+        .map(|(i, ((aad, msg, ct, tag), result))| {
+            json!({
+                "tcId": i + 1,
+                "comment": "",
+                "key": counting,
+                "iv": counting,
+                "aad": aad,
+                "msg": msg,
+                "ct": ct,
+                "tag": tag,
+                "result": result,
+            })
+        })
+        .collect();
+    vec![json!({ "type": "AeadTest", "tests": tests })]
+}
+
+fn photon_beetle_seal(key: &[u8], iv: &[u8], aad: &[u8], msg: &[u8]) -> Option<Vec<u8>> {
+    Some(photon_beetle_128_encrypt(
+        key.try_into().ok()?,
+        iv.try_into().ok()?,
+        aad,
+        msg,
+    ))
+}
+
+fn photon_beetle_open(key: &[u8], iv: &[u8], aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    photon_beetle_128_decrypt(key.try_into().ok()?, iv.try_into().ok()?, aad, sealed).ok()
+}
+
+#[test]
+fn aead_acceptable_test() {
+    // Acceptable cases pass whether they are opened or rejected
+    for results in [
+        ["valid", "invalid", "valid", "invalid"],
+        ["acceptable", "acceptable", "acceptable", "acceptable"],
+    ] {
+        run_aead(
+            "photon_beetle",
+            &photon_beetle_groups(results),
+            photon_beetle_seal,
+            photon_beetle_open,
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "photon_beetle tcId 2 () was rejected")]
+fn aead_wrong_result_test() {
+    run_aead(
+        "photon_beetle",
+        &photon_beetle_groups(["valid", "valid", "valid", "invalid"]),
+        photon_beetle_seal,
+        photon_beetle_open,
+    );
+}
+
+#[test]
+#[ignore = "needs the Wycheproof files in test_vectors/wycheproof"]
+fn wycheproof_ed25519_test() {
+    let name = "ed25519_test.json";
+    for group in load(name) {
+        // Older files call the key "key", newer ones "publicKey"
+        let key = group
+            .get("publicKey")
+            .unwrap_or_else(|| field(&group, "key"));
+        let public_key: [u8; 32] = hex(key, "pk").try_into().expect("32 byte public key");
+        for test in array(&group, "tests") {
+            let accepted = match hex(test, "sig").try_into() {
+                Ok(signature) => {
+                    Ed25519_verify_sign(&hex(test, "msg"), signature, public_key).is_ok()
+                }
+                Err(_) => false,
+            };
+            check_result(name, test, accepted);
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs the Wycheproof files in test_vectors/wycheproof"]
+fn wycheproof_aes_cbc_test() {
+    run_ind_cpa("aes_cbc_pkcs5_test.json", |key, iv, msg, ct| {
+        with_aes_key!(cbc, key, iv, msg, ct)
+    });
+}